@@ -0,0 +1,37 @@
+#!/bin/sh
+# Vendors the official collation test data from the root of the repository:
+#
+#     sh data/fetch-collation-test.sh
+#
+# This keeps the version header of CollationTest_NON_IGNORABLE_SHORT.txt and
+# CollationTest_SHIFTED_SHORT.txt, and the lines the collator can handle: ones
+# without surrogates, and without characters added after Unicode 9.0 that
+# decompose or have a non-zero combining class, as unicode-normalization
+# predates them. The version must match data/allkeys.txt.
+
+set -e
+
+version=13.0.0
+dir=$(mktemp -d)
+trap 'rm -rf "$dir"' EXIT
+
+curl -sSfL -o "$dir/CollationTest.zip" "https://www.unicode.org/Public/UCA/$version/CollationTest.zip"
+unzip -q -j -d "$dir" "$dir/CollationTest.zip"
+
+for variable in NON_IGNORABLE SHIFTED; do
+    file=CollationTest_${variable}_SHORT.txt
+    perl -MUnicode::UCD=prop_invlist,search_invlist -MUnicode::Normalize=NFD,getCombinClass -ne '
+        BEGIN { @unicode_9 = prop_invlist("In=9.0") }
+        if (/^#/ || /^\s*$/) { print; next }
+        my ($cps) = /^([0-9A-F ]+);/ or next;
+        my @cps = map { hex } split " ", $cps;
+        next if grep { $_ >= 0xD800 && $_ <= 0xDFFF } @cps;
+        my $supported = 1;
+        for my $c (map { chr } @cps) {
+            next if NFD($c) eq $c and getCombinClass(ord $c) == 0;
+            my $i = search_invlist(\@unicode_9, ord $c);
+            $supported = 0 unless defined $i and $i % 2 == 0;
+        }
+        print if $supported;
+    ' "$dir/$file" > "data/$file"
+done
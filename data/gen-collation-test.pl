@@ -0,0 +1,65 @@
+# Regenerates the collation test data from the root of the repository:
+#
+#     perl data/gen-collation-test.pl non-ignorable > data/collation-test-non-ignorable.txt
+#     perl data/gen-collation-test.pl shifted > data/collation-test-shifted.txt
+
+use strict; use warnings;
+use Unicode::Collate;
+use Unicode::UCD qw(prop_invlist search_invlist);
+use Unicode::Normalize qw(NFD getCombinClass);
+# unicode-normalization only knows Unicode 9.0, so leave out characters added
+# later that it would fail to decompose or reorder
+my @unicode_9 = prop_invlist('In=9.0');
+sub normalizable {
+    for my $c (split //, $_[0]) {
+        next if NFD($c) eq $c and getCombinClass(ord $c) == 0;
+        my $i = search_invlist(\@unicode_9, ord $c);
+        return 0 unless defined $i and $i % 2 == 0;
+    }
+    return 1;
+}
+binmode STDOUT, ':utf8';
+my ($variable) = @ARGV;
+my @strings;
+open my $fh, '<', 'data/allkeys.txt' or die;
+my $n = 0;
+while (<$fh>) {
+    next unless /^([0-9A-F ]+?)\s*;/;
+    my @cps = map { hex } split ' ', $1;
+    next if grep { $_ >= 0xD800 && $_ <= 0xDFFF } @cps;
+    my $s = join '', map { chr } @cps;
+    push @strings, $s;
+    # a few suffixes, like the official test data
+    if ($n++ % 5 == 0) {
+        push @strings, $s . 'b', $s . '!', $s . "\x{0301}";
+    }
+}
+# implicit weights: CJK, extensions, Tangut, Nushu, Khitan and unassigned
+push @strings, map { chr } (0x4E00, 0x4E01, 0x9FA5, 0x9FFC, 0xFA0E, 0xFA10, 0xFA11,
+    0x3400, 0x4DBF, 0x20000, 0x2A6DD, 0x2B740, 0x30000, 0x3134A, 0x17000, 0x18AFF,
+    0x18D00, 0x1B170, 0x18B00, 0x0378, 0x0378 + 1, 0xE0080, 0x10FFFD, 0xFFFE);
+# Hangul syllables, which are decomposed into jamo
+push @strings, map { chr } (0xAC00, 0xAC01, 0xAC1C, 0xB098, 0xD7A3);
+# combining marks in different orders, and marks interrupting contractions
+for my $base ('a', 'e', 'o', 'A', "\x{0B95}\x{0BCD}\x{0BB7}", "\x{0418}", "\x{0438}", "L\x{00B7}", "l\x{00B7}") {
+    for my $marks ("\x{0323}\x{0302}", "\x{0302}\x{0323}", "\x{0316}\x{0301}", "\x{0301}\x{0316}",
+                   "\x{0308}", "\x{0306}", "\x{0316}\x{0306}", "\x{031B}\x{0306}") {
+        push @strings, $base . $marks, $base . $marks . 'b';
+    }
+}
+# names like the ones on the release pages
+push @strings, "Anne Marie", "Anne-Marie", "AnneMarie", "anne marie", "Ánne Marie",
+    "Zoë", "Zoe", "zoe", "Ærøskøbing", "Aaron", "aaron", "Æsir", "Œuvre", "ﬁle", "file",
+    "Ödön", "Odon", "Łukasz", "Lukasz", "Ђорђе", "Джон", "Дмитрий", "Ёлка", "Елка",
+    "李", "王", "張", "안녕", "ひらがな", "カタカナ", "Ω", "ω", "Σ", "ς", "σ", "ß", "ss", "SS";
+my $c = Unicode::Collate->new(variable => $variable, level => ($variable eq 'shifted' ? 4 : 3), identical => 1);
+my %seen;
+@strings = grep { !$seen{$_}++ && normalizable($_) } @strings;
+print "# Generated with Perl's Unicode::Collate $Unicode::Collate::VERSION (UCA ", $c->version, "),\n";
+print "# variable => '$variable', identical => 1, from data/allkeys.txt.\n";
+print "# Characters added after Unicode 9.0 that decompose or have a non-zero\n";
+print "# combining class are left out, as unicode-normalization predates them.\n";
+print "# Each line is a string as hex code points; lines are in sorted order.\n";
+for my $s ($c->sort(@strings)) {
+    print join(' ', map { sprintf '%04X', ord } split //, $s), "\n";
+}
@@ -0,0 +1,148 @@
+#!/bin/sh
+# Regenerates the mailmap test data from the root of the repository:
+#
+#     sh data/gen-mailmap-test.sh > data/mailmap-test.txt
+#
+# Each case is a .mailmap and the contacts to look up in it, which are run
+# through `git check-mailmap` to record what git maps them to.
+
+set -e
+
+dir=$(mktemp -d)
+trap 'rm -rf "$dir"' EXIT
+
+# leave the user's configuration, and any mailmap.file it sets, out of it
+export HOME="$dir" XDG_CONFIG_HOME="$dir" GIT_CONFIG_NOSYSTEM=1
+git init -q "$dir/repo"
+
+echo "# Generated with $(git --version) by data/gen-mailmap-test.sh."
+echo "# Each case starts with '@@ name', then the .mailmap up to '--', then one"
+echo "# contact per line with what git maps it to, separated by a tab."
+
+# case NAME CONTACT... < MAILMAP
+case_() {
+    name=$1
+    shift
+    cat > "$dir/repo/.mailmap"
+
+    echo
+    echo "@@ $name"
+    cat "$dir/repo/.mailmap"
+    echo "--"
+    for contact in "$@"; do
+        printf '%s\t%s\n' "$contact" "$(git -C "$dir/repo" check-mailmap "$contact")"
+    done
+}
+
+case_ "simple forms" \
+    "Wrong Name <othername@example.com>" \
+    "Aaron Hill <commitemail@example.com>" \
+    "Some Person <fakejones@example.com>" \
+    "Not Here <nothere@example.com>" <<'EOF'
+Other Name <othername@example.com>
+<properemail@example.com> <commitemail@example.com>
+Bob Jones <bobjones@example.com> <fakejones@example.com>
+EOF
+
+case_ "name and email" \
+    "That Guy <thatguy@example.com>" \
+    "Other Guy <thatguy@example.com>" \
+    "That Guy <blah@example.com>" \
+    "Old Name <old@example.com>" \
+    "Older Name <old@example.com>" <<'EOF'
+John Doe <johndoe@example.com> That Guy <thatguy@example.com>
+<new@example.com> Old Name <old@example.com>
+EOF
+
+case_ "name and email over email only" \
+    "Ana <ana@example.com>" \
+    "ana lopez <ana@example.com>" \
+    "Someone <ana@example.com>" <<'EOF'
+Ana López <ana@example.com> ana lopez <ana@example.com>
+Ana L. <ana@work.example.com> <ana@example.com>
+EOF
+
+case_ "name and email over email only, listed after" \
+    "ana lopez <ana@example.com>" \
+    "Someone <ana@example.com>" <<'EOF'
+Ana L. <ana@work.example.com> <ana@example.com>
+Ana López <ana@example.com> ana lopez <ana@example.com>
+EOF
+
+case_ "later entries win" \
+    "Jo <jo@example.com>" \
+    "Sam <sam@example.com>" \
+    "sam lee <sam@example.com>" <<'EOF'
+Jo Smith <jo@example.com>
+Jo Jones <jo@example.com>
+<sam@example.org> <sam@example.com>
+Sam Lee <sam@example.com>
+Sam Lee <sam@example.net> sam lee <sam@example.com>
+Samuel Lee <sam@example.info> SAM LEE <sam@example.com>
+EOF
+
+case_ "case" \
+    "WRONG <OtHername@exAmple.com>" \
+    "THAT guy <ThATguy@examPle.com>" \
+    "ÄNA <ana@example.com>" \
+    "äna <ana@example.com>" <<'EOF'
+Other Name <othername@example.com>
+John Doe <johndoe@example.com> That Guy <thatguy@example.com>
+Ana <ana@example.com> äna <ana@example.com>
+EOF
+
+case_ "comments" \
+    "Three <threefour@example.com>" \
+    "One <fivesix@example.com>" \
+    "Ana <ana@example.com>" \
+    "# comment <seven@example.com>" \
+    "Seven <seven@example.com>" \
+    "x <commented@example.com>" \
+    "x <indented@example.com>" <<'EOF'
+# Comment line!
+Three Four <threefour@example.com> # This is a comment
+Five # Six <fivesix@example.com>
+Ana <ana@example.com> # comment <seven@example.com>
+#Commented Out <commented@example.com>
+  # Indented <indented@example.com>
+EOF
+
+case_ "whitespace" \
+    "x <spaced@example.com>" \
+    "x <tabbed@example.com>" \
+    "x < padded@example.com >" \
+    "x <padded@example.com>" \
+    "x <nameless@example.com>" <<'EOF'
+   Spaced   Out   <spaced@example.com>
+	Tabbed	<tabbed@example.com>
+Padded < padded@example.com >
+    <nameless@example.com>
+EOF
+
+# git's isspace leaves out vertical tabs and form feeds, so they're part of
+# the name; they're written with printf to keep them out of this file
+vt=$(printf '\v')
+ff=$(printf '\f')
+case_ "vertical tab and form feed" \
+    "x <vtab@example.com>" \
+    "x <formfeed@example.com>" \
+    "x <blank@example.com>" <<EOF
+${vt}Vertical Tab${vt} <vtab@example.com>
+Form Feed ${ff}<formfeed@example.com>
+${vt}${ff} <blank@example.com>
+EOF
+
+case_ "malformed lines" \
+    "x <valid@example.com>" \
+    "Also Valid <alsovalid@example.com>" \
+    "Not Valid <notvalid@example.com>" \
+    "x <unclosed@example.com>" \
+    "x <noemail@example.com>" \
+    "x <>" <<'EOF'
+Am Valid <valid@example.com> Also Valid <alsovalid@example.com> Not Valid <notvalid@example.com>
+Unclosed <unclosed@example.com
+No Email <>
+No Email <noemail@example.com> <>
+just a name
+<only@example.com>
+EOF
@@ -0,0 +1,5 @@
+DROP INDEX commits_patch_id_idx;
+
+ALTER TABLE commits
+	DROP COLUMN backport_of,
+	DROP COLUMN patch_id;
@@ -0,0 +1,7 @@
+ALTER TABLE commits
+	ADD COLUMN patch_id VARCHAR,
+	ADD COLUMN backport_of VARCHAR
+	REFERENCES commits (sha)
+	ON DELETE SET NULL;
+
+CREATE INDEX commits_patch_id_idx ON commits USING btree (patch_id);
@@ -0,0 +1,3 @@
+DROP TABLE channels;
+
+ALTER TABLE releases DROP COLUMN pseudo;
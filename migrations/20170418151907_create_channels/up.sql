@@ -0,0 +1,18 @@
+ALTER TABLE releases ADD COLUMN pseudo BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE releases SET pseudo = TRUE WHERE version = 'master';
+
+CREATE TABLE channels (
+	id SERIAL PRIMARY KEY,
+	project_id INTEGER NOT NULL
+	REFERENCES projects (id)
+	ON DELETE CASCADE,
+	release_id INTEGER NOT NULL
+	REFERENCES releases (id)
+	ON DELETE CASCADE,
+	name VARCHAR NOT NULL,
+	branch VARCHAR NOT NULL,
+	since VARCHAR
+);
+
+CREATE UNIQUE INDEX channels_project_id_name_idx ON channels (project_id, name);
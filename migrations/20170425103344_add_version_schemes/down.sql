@@ -0,0 +1,3 @@
+ALTER TABLE releases DROP COLUMN released_at;
+
+ALTER TABLE projects DROP COLUMN version_scheme;
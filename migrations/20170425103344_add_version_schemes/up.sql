@@ -0,0 +1,3 @@
+ALTER TABLE projects ADD COLUMN version_scheme VARCHAR NOT NULL DEFAULT 'lenient-semver';
+
+ALTER TABLE releases ADD COLUMN released_at BIGINT;
@@ -0,0 +1,3 @@
+ALTER TABLE projects
+	DROP COLUMN last_synced,
+	DROP COLUMN repo_path;
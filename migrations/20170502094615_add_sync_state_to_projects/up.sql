@@ -0,0 +1,3 @@
+ALTER TABLE projects
+	ADD COLUMN repo_path VARCHAR,
+	ADD COLUMN last_synced VARCHAR;
@@ -0,0 +1,2 @@
+ALTER TABLE projects
+	ADD COLUMN github_etag VARCHAR;
@@ -0,0 +1,4 @@
+ALTER TABLE commits DROP COLUMN repository_id;
+
+DROP TABLE release_tags;
+DROP TABLE repositories;
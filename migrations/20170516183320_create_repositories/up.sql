@@ -0,0 +1,29 @@
+CREATE TABLE repositories (
+	id SERIAL PRIMARY KEY,
+	project_id INTEGER NOT NULL
+	REFERENCES projects (id)
+	ON DELETE CASCADE,
+	name VARCHAR NOT NULL,
+	github_name VARCHAR NOT NULL,
+	repo_path VARCHAR NOT NULL
+);
+
+CREATE UNIQUE INDEX repositories_project_id_name_idx ON repositories (project_id, name);
+
+CREATE TABLE release_tags (
+	id SERIAL PRIMARY KEY,
+	release_id INTEGER NOT NULL
+	REFERENCES releases (id)
+	ON DELETE CASCADE,
+	repository_id INTEGER NOT NULL
+	REFERENCES repositories (id)
+	ON DELETE CASCADE,
+	tag VARCHAR NOT NULL
+);
+
+CREATE UNIQUE INDEX release_tags_release_id_repository_id_idx ON release_tags (release_id, repository_id);
+
+ALTER TABLE commits
+	ADD COLUMN repository_id INTEGER
+	REFERENCES repositories (id)
+	ON DELETE CASCADE;
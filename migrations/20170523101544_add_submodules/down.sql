@@ -0,0 +1,6 @@
+ALTER TABLE repositories
+	DROP COLUMN included,
+	DROP COLUMN submodule_path;
+
+ALTER TABLE projects
+	DROP COLUMN follow_submodules;
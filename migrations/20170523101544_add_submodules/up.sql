@@ -0,0 +1,8 @@
+ALTER TABLE projects
+	ADD COLUMN follow_submodules BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE repositories
+	ADD COLUMN submodule_path VARCHAR,
+	ADD COLUMN included BOOLEAN NOT NULL DEFAULT TRUE;
+
+CREATE UNIQUE INDEX repositories_project_id_submodule_path_idx ON repositories (project_id, submodule_path);
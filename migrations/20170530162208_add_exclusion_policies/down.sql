@@ -0,0 +1,8 @@
+DROP TABLE bots;
+
+ALTER TABLE commits
+	DROP COLUMN excluded,
+	DROP COLUMN is_merge;
+
+ALTER TABLE projects
+	DROP COLUMN skip_merges;
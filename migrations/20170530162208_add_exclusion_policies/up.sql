@@ -0,0 +1,15 @@
+ALTER TABLE projects
+	ADD COLUMN skip_merges BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE commits
+	ADD COLUMN is_merge BOOLEAN,
+	ADD COLUMN excluded BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE bots (
+	id SERIAL PRIMARY KEY,
+	project_id INTEGER NOT NULL
+	REFERENCES projects (id)
+	ON DELETE CASCADE,
+	name_pattern VARCHAR,
+	email_pattern VARCHAR
+);
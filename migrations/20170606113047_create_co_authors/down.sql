@@ -0,0 +1 @@
+DROP TABLE co_authors;
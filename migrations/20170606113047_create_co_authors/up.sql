@@ -0,0 +1,9 @@
+CREATE TABLE co_authors (
+	commit_sha VARCHAR NOT NULL
+	REFERENCES commits (sha)
+	ON DELETE CASCADE,
+	author_id INTEGER NOT NULL
+	REFERENCES authors (id)
+	ON DELETE CASCADE,
+	PRIMARY KEY (commit_sha, author_id)
+);
@@ -0,0 +1,17 @@
+CREATE TABLE reviews (
+	commit_sha VARCHAR NOT NULL
+	REFERENCES commits (sha)
+	ON DELETE CASCADE,
+	reviewer VARCHAR NOT NULL,
+	author_id INTEGER
+	REFERENCES authors (id)
+	ON DELETE SET NULL,
+	PRIMARY KEY (commit_sha, reviewer)
+);
+
+CREATE TABLE handles (
+	handle VARCHAR PRIMARY KEY,
+	author_id INTEGER NOT NULL
+	REFERENCES authors (id)
+	ON DELETE CASCADE
+);
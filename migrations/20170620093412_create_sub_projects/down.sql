@@ -0,0 +1,5 @@
+DROP TABLE commit_scopes;
+DROP TABLE scope_paths;
+
+ALTER TABLE projects
+	DROP COLUMN parent_id;
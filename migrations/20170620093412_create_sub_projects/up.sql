@@ -0,0 +1,22 @@
+ALTER TABLE projects
+	ADD COLUMN parent_id INTEGER
+	REFERENCES projects (id)
+	ON DELETE CASCADE;
+
+CREATE TABLE scope_paths (
+	id SERIAL PRIMARY KEY,
+	project_id INTEGER NOT NULL
+	REFERENCES projects (id)
+	ON DELETE CASCADE,
+	glob VARCHAR NOT NULL
+);
+
+CREATE TABLE commit_scopes (
+	commit_sha VARCHAR NOT NULL
+	REFERENCES commits (sha)
+	ON DELETE CASCADE,
+	project_id INTEGER NOT NULL
+	REFERENCES projects (id)
+	ON DELETE CASCADE,
+	PRIMARY KEY (commit_sha, project_id)
+);
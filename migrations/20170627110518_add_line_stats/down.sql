@@ -0,0 +1,6 @@
+DROP TABLE ignored_paths;
+
+ALTER TABLE commits
+	DROP COLUMN files_changed,
+	DROP COLUMN deletions,
+	DROP COLUMN insertions;
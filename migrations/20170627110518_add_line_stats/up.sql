@@ -0,0 +1,12 @@
+ALTER TABLE commits
+	ADD COLUMN insertions INTEGER,
+	ADD COLUMN deletions INTEGER,
+	ADD COLUMN files_changed INTEGER;
+
+CREATE TABLE ignored_paths (
+	id SERIAL PRIMARY KEY,
+	project_id INTEGER NOT NULL
+	REFERENCES projects (id)
+	ON DELETE CASCADE,
+	glob VARCHAR NOT NULL
+);
@@ -0,0 +1,8 @@
+CREATE TABLE author_aliases (
+	name VARCHAR NOT NULL,
+	email VARCHAR NOT NULL,
+	author_id INTEGER NOT NULL
+	REFERENCES authors (id)
+	ON DELETE CASCADE,
+	PRIMARY KEY (name, email)
+);
@@ -0,0 +1,4 @@
+ALTER TABLE authors
+	DROP COLUMN person_id;
+
+DROP TABLE people;
@@ -0,0 +1,9 @@
+CREATE TABLE people (
+	id SERIAL PRIMARY KEY,
+	name VARCHAR NOT NULL UNIQUE
+);
+
+ALTER TABLE authors
+	ADD COLUMN person_id INTEGER
+	REFERENCES people (id)
+	ON DELETE SET NULL;
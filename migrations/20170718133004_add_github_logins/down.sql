@@ -0,0 +1,5 @@
+ALTER TABLE authors
+	DROP COLUMN github_login;
+
+ALTER TABLE people
+	DROP COLUMN github_login;
@@ -0,0 +1,5 @@
+ALTER TABLE authors
+	ADD COLUMN github_login VARCHAR;
+
+ALTER TABLE people
+	ADD COLUMN github_login VARCHAR;
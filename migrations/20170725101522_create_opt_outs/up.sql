@@ -0,0 +1,12 @@
+CREATE TABLE opt_outs (
+	id SERIAL PRIMARY KEY,
+	email VARCHAR,
+	email_pattern VARCHAR,
+	name VARCHAR,
+	person_id INTEGER
+	REFERENCES people (id)
+	ON DELETE CASCADE,
+	reason VARCHAR NOT NULL,
+	created_at BIGINT NOT NULL,
+	CHECK (num_nonnulls(email, email_pattern, name, person_id) = 1)
+);
@@ -0,0 +1,4 @@
+CREATE TABLE name_overrides (
+	name VARCHAR PRIMARY KEY,
+	display_name VARCHAR NOT NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE authors
+	ADD COLUMN anonymous BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,3 @@
+ALTER TABLE projects
+	DROP COLUMN branch,
+	DROP COLUMN branch_title;
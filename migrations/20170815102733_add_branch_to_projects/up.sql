@@ -0,0 +1,3 @@
+ALTER TABLE projects
+	ADD COLUMN branch VARCHAR NOT NULL DEFAULT 'master',
+	ADD COLUMN branch_title VARCHAR;
@@ -0,0 +1,7 @@
+UPDATE commits
+	SET release_id = channels.release_id
+	FROM channel_commits, channels
+	WHERE channel_commits.commit_sha = commits.sha
+	AND channels.id = channel_commits.channel_id;
+
+DROP TABLE channel_commits;
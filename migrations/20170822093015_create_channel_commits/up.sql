@@ -0,0 +1,24 @@
+CREATE TABLE channel_commits (
+	channel_id INTEGER NOT NULL
+	REFERENCES channels (id)
+	ON DELETE CASCADE,
+	commit_sha VARCHAR NOT NULL
+	REFERENCES commits (sha)
+	ON DELETE CASCADE,
+	PRIMARY KEY (channel_id, commit_sha)
+);
+
+-- channels used to take their commits away from the project's branch
+INSERT INTO channel_commits (channel_id, commit_sha)
+	SELECT channels.id, commits.sha
+	FROM channels
+	INNER JOIN commits ON commits.release_id = channels.release_id;
+
+UPDATE commits
+	SET release_id = branch_releases.id
+	FROM channels, projects, releases AS branch_releases
+	WHERE commits.release_id = channels.release_id
+	AND projects.id = channels.project_id
+	AND branch_releases.project_id = projects.id
+	AND branch_releases.version = projects.branch
+	AND branch_releases.pseudo;
@@ -0,0 +1 @@
+DELETE FROM opt_outs WHERE reason = 'hidden before opt-outs were kept';
@@ -0,0 +1,7 @@
+-- authors hidden before opt-outs were kept as rules get one by their email,
+-- so that opting them back in works like for everyone else
+INSERT INTO opt_outs (email, reason, created_at)
+	SELECT DISTINCT email, 'hidden before opt-outs were kept', EXTRACT(EPOCH FROM now())::BIGINT
+	FROM authors
+	WHERE NOT visible
+	AND email NOT IN (SELECT email FROM opt_outs WHERE email IS NOT NULL);
@@ -0,0 +1,11 @@
+ALTER TABLE name_overrides RENAME TO author_name_overrides;
+CREATE TABLE name_overrides (
+	name VARCHAR PRIMARY KEY,
+	display_name VARCHAR NOT NULL
+);
+INSERT INTO name_overrides (name, display_name)
+	SELECT DISTINCT ON (authors.name) authors.name, author_name_overrides.display_name
+	FROM author_name_overrides
+	INNER JOIN authors ON authors.id = author_name_overrides.author_id
+	ORDER BY authors.name, authors.id;
+DROP TABLE author_name_overrides;
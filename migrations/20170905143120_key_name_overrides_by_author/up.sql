@@ -0,0 +1,14 @@
+-- overrides belong to the authors that had the name, rather than to anyone
+-- who commits under it
+ALTER TABLE name_overrides RENAME TO old_name_overrides;
+CREATE TABLE name_overrides (
+	author_id INTEGER PRIMARY KEY
+	REFERENCES authors (id)
+	ON DELETE CASCADE,
+	display_name VARCHAR NOT NULL
+);
+INSERT INTO name_overrides (author_id, display_name)
+	SELECT authors.id, old_name_overrides.display_name
+	FROM old_name_overrides
+	INNER JOIN authors ON authors.name = old_name_overrides.name;
+DROP TABLE old_name_overrides;
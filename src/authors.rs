@@ -1,5 +1,7 @@
-use models::{Author, NewAuthor};
+use models::{Author, AuthorAlias, NewAuthor, NewAuthorAlias, NewCoAuthor};
+use models::{NameOverride, NewNameOverride};
 use mailmap::Mailmap;
+use opt_outs::OptOuts;
 
 use diesel::*;
 use diesel::pg::PgConnection;
@@ -7,18 +9,63 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufReader;
+use git2;
 use git2::Repository;
 use std::path::Path;
 
+use regex::Regex;
+use slog::Logger;
+use unicode_normalization::UnicodeNormalization;
+
 use releases;
 
 // Postgresql won't execute the query if this is much higher
 const ITEMS_PER_CHUNK: usize = 30_000;
 
+lazy_static! {
+    // the email GitHub commits with for people who keep theirs private; newer
+    // ones start with the account id, like `12345+login@...`
+    static ref NOREPLY_EMAIL: Regex = Regex::new(
+        r"(?i)^(?:\d+\+)?([a-z\d](?:[a-z\d-]{0,37}[a-z\d])?)@users\.noreply\.github\.com$"
+    ).unwrap();
+}
+
+/// the form identities are stored in, so someone whose name or email is
+/// written two ways is still one author
+///
+/// Names are put in NFC, as the same name can come in composed and decomposed
+/// forms. Emails are trimmed and their domain, which is case-insensitive, put
+/// in lower case. The part before the `@` is left alone, as mail servers are
+/// free to tell its cases apart.
+pub fn normalize(author_name: &str, author_email: &str) -> (String, String) {
+    let author_name: String = author_name.trim().nfc().collect();
+
+    let author_email = author_email.trim();
+    let author_email = match author_email.rfind('@') {
+        Some(at) => format!(
+            "{}{}",
+            &author_email[..at],
+            author_email[at..].to_lowercase()
+        ),
+        None => author_email.to_string(),
+    };
+
+    (author_name, author_email)
+}
+
+/// the GitHub login in a `users.noreply.github.com` email
+pub fn github_login(email: &str) -> Option<&str> {
+    NOREPLY_EMAIL
+        .captures(email)
+        .and_then(|cap| cap.get(1))
+        .map(|login| login.as_str())
+}
+
 pub struct AuthorStore<'a> {
     cache: HashMap<(String, String), Author>,
     conn: &'a PgConnection,
     mailmap: Mailmap,
+    opt_outs: OptOuts,
 }
 
 impl<'a> AuthorStore<'a> {
@@ -27,6 +74,7 @@ impl<'a> AuthorStore<'a> {
             cache: HashMap::new(),
             conn: conn,
             mailmap: mailmap,
+            opt_outs: OptOuts::load(conn),
         }
     }
 
@@ -46,25 +94,40 @@ impl<'a> AuthorStore<'a> {
             }
         };
 
-        AuthorStore {
-            cache: HashMap::new(),
-            conn: conn,
-            mailmap: Mailmap::new(contents.as_str()),
-        }
+        AuthorStore::new(conn, Mailmap::new(contents.as_str()))
+    }
+
+    /// uses the `.mailmap` in a commit, rather than the one in the working
+    /// directory, which may not have been updated after a fetch
+    pub fn from_commit(
+        conn: &'a PgConnection,
+        repo: &Repository,
+        commit: &git2::Commit,
+    ) -> AuthorStore<'a> {
+        let contents = commit
+            .tree()
+            .ok()
+            .and_then(|tree| tree.get_name(".mailmap").map(|entry| entry.id()))
+            .and_then(|id| repo.find_blob(id).ok())
+            .map(|blob| String::from_utf8_lossy(blob.content()).into_owned())
+            .unwrap_or_else(String::new);
+
+        AuthorStore::new(conn, Mailmap::new(contents.as_str()))
     }
 
     pub fn get(&mut self, author_name: &str, author_email: &str) -> Author {
+        let entry = normalize(author_name, author_email);
+
         let new_author = NewAuthor {
-            name: author_name,
-            email: author_email,
+            name: &entry.0,
+            email: &entry.1,
+            github_login: github_login(&entry.1),
         };
 
-        let entry = (author_name.to_string(), author_email.to_string());
-
         if !self.cache.contains_key(&entry) {
             let author = self.find_or_create(&new_author)
-                .expect("Could not find or create author")
-                .clone();
+                .expect("Could not find or create author");
+            let author = self.opt_outs.enforce(self.conn, author);
             self.cache.insert(entry.clone(), author);
         }
         self.cache.get(&entry).unwrap().clone()
@@ -83,6 +146,7 @@ impl<'a> AuthorStore<'a> {
         // This is more efficient than querying the DB for each author individually
         for author in new_authors.into_iter() {
             let (m_name, m_email) = self.mailmap.map(author.name, author.email);
+            let (m_name, m_email) = normalize(&m_name, &m_email);
 
             match self.cache.get(&(m_name.clone(), m_email.clone())) {
                 Some(a) => found.push(a.clone()),
@@ -94,6 +158,37 @@ impl<'a> AuthorStore<'a> {
             };
         }
 
+        // identities that were merged into another author resolve to it
+        if !missing.is_empty() {
+            let aliased = aliases(self.conn, &missing_names, &missing_emails);
+            let mut unaliased = Vec::new();
+            let mut unaliased_names = Vec::new();
+            let mut unaliased_emails = Vec::new();
+
+            for ((author, m_name), m_email) in missing
+                .into_iter()
+                .zip(missing_names)
+                .zip(missing_emails)
+            {
+                match aliased.get(&(m_name.clone(), m_email.clone())) {
+                    Some(a) => {
+                        let a = self.opt_outs.enforce(self.conn, a.clone());
+                        found.push(a.clone());
+                        self.cache.insert((m_name, m_email), a);
+                    }
+                    None => {
+                        unaliased.push(author);
+                        unaliased_names.push(m_name);
+                        unaliased_emails.push(m_email);
+                    }
+                }
+            }
+
+            missing = unaliased;
+            missing_names = unaliased_names;
+            missing_emails = unaliased_emails;
+        }
+
         if !missing.is_empty() {
             missing
                 .chunks(ITEMS_PER_CHUNK)
@@ -105,8 +200,20 @@ impl<'a> AuthorStore<'a> {
                     let the_names = &missing_names[start..end];
                     let the_emails = &missing_emails[start..end];
 
+                    // the identities as they're stored, rather than as they
+                    // came in
+                    let new_authors: Vec<_> = the_names
+                        .iter()
+                        .zip(the_emails)
+                        .map(|(the_name, the_email)| NewAuthor {
+                            name: the_name,
+                            email: the_email,
+                            github_login: ::authors::github_login(the_email),
+                        })
+                        .collect();
+
                     insert_into(authors)
-                        .values(chunk)
+                        .values(&new_authors)
                         .on_conflict_do_nothing()
                         .execute(self.conn)
                         .unwrap();
@@ -118,6 +225,8 @@ impl<'a> AuthorStore<'a> {
                         .unwrap();
 
                     for new_author in db_authors.into_iter() {
+                        // opt-outs cover new identities of people too
+                        let new_author = self.opt_outs.enforce(self.conn, new_author);
                         found.push(new_author.clone());
 
                         self.cache.insert(
@@ -132,8 +241,9 @@ impl<'a> AuthorStore<'a> {
         found
     }
 
-    pub fn warm_cache(&mut self, repo: &Repository) {
-        let commits = releases::get_first_commits(repo, "master");
+    /// creates the authors of every commit on `branch` at once
+    pub fn warm_cache(&mut self, repo: &Repository, branch: &str) {
+        let commits = releases::get_first_commits(repo, branch);
 
         let authors: Vec<_> = commits
             .into_iter()
@@ -150,6 +260,7 @@ impl<'a> AuthorStore<'a> {
             .map(|&(ref name, ref email)| NewAuthor {
                 name: name.as_str(),
                 email: email.as_str(),
+                github_login: github_login(email),
             })
             .collect();
 
@@ -164,10 +275,22 @@ impl<'a> AuthorStore<'a> {
         &self.mailmap
     }
 
+    /// the connection authors are looked up and created with
+    pub fn connection(&self) -> &'a PgConnection {
+        self.conn
+    }
+
     fn find_or_create(&self, new_author: &NewAuthor) -> QueryResult<Author> {
         use schema::authors::dsl::*;
         use diesel::pg::upsert::*;
 
+        let identity = (new_author.name.to_string(), new_author.email.to_string());
+        if let Some(author) = aliases(self.conn, &[identity.0.clone()], &[identity.1.clone()])
+            .remove(&identity)
+        {
+            return Ok(author);
+        }
+
         let maybe_inserted = insert_into(authors)
             .values(new_author)
             .on_conflict_do_nothing()
@@ -184,3 +307,282 @@ impl<'a> AuthorStore<'a> {
             .first(self.conn)
     }
 }
+
+/// the authors that identities were merged into, by identity
+fn aliases(
+    conn: &PgConnection,
+    names: &[String],
+    emails: &[String],
+) -> HashMap<(String, String), Author> {
+    use schema::{author_aliases, authors};
+    use diesel::expression::dsl::any;
+
+    author_aliases::table
+        .inner_join(authors::table)
+        .filter(author_aliases::name.eq(any(names)))
+        .filter(author_aliases::email.eq(any(emails)))
+        .load::<(AuthorAlias, Author)>(conn)
+        .expect("Error loading author aliases")
+        .into_iter()
+        .map(|(alias, author)| ((alias.name, alias.email), author))
+        .collect()
+}
+
+/// merges the author `from` into `into`, and returns `into` as it is after
+///
+/// Everything credited to `from` moves over, and `into` stays hidden if either
+/// of them opted out, and anonymous if either of them is. `from` is deleted,
+/// but its identity is kept as an alias, so importing its commits again finds
+/// `into` instead of bringing it back.
+pub fn merge(conn: &PgConnection, from: &Author, into: &Author) -> Author {
+    use schema::{author_aliases, authors, co_authors, commits, handles, name_overrides, reviews};
+    use diesel::expression::dsl::any;
+    use diesel::pg::upsert::*;
+
+    conn.transaction::<_, result::Error, _>(|| {
+        update(commits::table.filter(commits::author_id.eq(from.id)))
+            .set(commits::author_id.eq(into.id))
+            .execute(conn)?;
+
+        let co_authored: Vec<String> = co_authors::table
+            .filter(co_authors::author_id.eq(from.id))
+            .select(co_authors::commit_sha)
+            .load(conn)?;
+        if !co_authored.is_empty() {
+            let moved: Vec<_> = co_authored
+                .iter()
+                .map(|sha| NewCoAuthor {
+                    commit_sha: sha,
+                    author_id: into.id,
+                })
+                .collect();
+            insert_into(co_authors::table)
+                .values(&moved)
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+            delete(co_authors::table.filter(co_authors::author_id.eq(from.id))).execute(conn)?;
+        }
+
+        // nobody co-authors their own commits
+        let own = commits::table
+            .filter(commits::author_id.eq(into.id))
+            .select(commits::sha);
+        delete(
+            co_authors::table
+                .filter(co_authors::author_id.eq(into.id))
+                .filter(co_authors::commit_sha.eq(any(own))),
+        ).execute(conn)?;
+
+        update(reviews::table.filter(reviews::author_id.eq(from.id)))
+            .set(reviews::author_id.eq(into.id))
+            .execute(conn)?;
+        update(handles::table.filter(handles::author_id.eq(from.id)))
+            .set(handles::author_id.eq(into.id))
+            .execute(conn)?;
+
+        // an override for `from` carries over, unless `into` has its own
+        let overridden: Option<NameOverride> = name_overrides::table
+            .find(from.id)
+            .first(conn)
+            .optional()?;
+        if let Some(overridden) = overridden {
+            insert_into(name_overrides::table)
+                .values(&NewNameOverride {
+                    author_id: into.id,
+                    display_name: &overridden.display_name,
+                })
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+        }
+
+        // earlier merges into `from` now lead to `into`
+        update(author_aliases::table.filter(author_aliases::author_id.eq(from.id)))
+            .set(author_aliases::author_id.eq(into.id))
+            .execute(conn)?;
+        delete(
+            author_aliases::table
+                .filter(author_aliases::name.eq(&from.name))
+                .filter(author_aliases::email.eq(&from.email)),
+        ).execute(conn)?;
+        insert_into(author_aliases::table)
+            .values(&NewAuthorAlias {
+                name: &from.name,
+                email: &from.email,
+                author_id: into.id,
+            })
+            .execute(conn)?;
+
+        delete(authors::table.find(from.id)).execute(conn)?;
+
+        update(authors::table.find(into.id))
+            .set((
+                authors::visible.eq(into.visible && from.visible),
+                authors::anonymous.eq(into.anonymous || from.anonymous),
+                authors::person_id.eq(into.person_id.or(from.person_id)),
+                authors::github_login
+                    .eq(into.github_login.as_ref().or(from.github_login.as_ref())),
+            ))
+            .get_result(conn)
+    }).expect("Error merging authors")
+}
+
+/// sets the GitHub login of an author, like for one whose emails don't give
+/// it away
+pub fn set_github_login(conn: &PgConnection, author: &Author, login: Option<&str>) {
+    use schema::authors::dsl::*;
+
+    update(authors.find(author.id))
+        .set(github_login.eq(login))
+        .execute(conn)
+        .expect("Error saving GitHub login");
+}
+
+/// gives authors imported before we looked at their emails the login in
+/// them, and returns how many got one
+pub fn fill_github_logins(conn: &PgConnection) -> usize {
+    use schema::authors::dsl::*;
+
+    let unlinked: Vec<(i32, String)> = authors
+        .filter(github_login.is_null())
+        .filter(email.like("%@users.noreply.github.com"))
+        .select((id, email))
+        .load(conn)
+        .expect("Error loading authors");
+
+    conn.transaction::<_, result::Error, _>(|| {
+        let mut filled = 0;
+        for &(author_id, ref author_email) in &unlinked {
+            if let Some(login) = ::authors::github_login(author_email) {
+                filled += update(authors.find(author_id))
+                    .set(github_login.eq(login))
+                    .execute(conn)?;
+            }
+        }
+        Ok(filled)
+    }).expect("Error saving GitHub logins")
+}
+
+/// brings authors imported before identities were normalized in line, and
+/// merges those that turn out to be the same; returns how many were merged
+///
+/// With `dry_run` it only logs what it would do.
+pub fn normalize_existing(log: &Logger, conn: &PgConnection, dry_run: bool) -> usize {
+    use schema::authors::dsl::*;
+    use std::collections::BTreeMap;
+
+    let all_authors: Vec<Author> = authors
+        .order(id)
+        .load(conn)
+        .expect("Error loading authors");
+
+    let mut groups: BTreeMap<(String, String), Vec<Author>> = BTreeMap::new();
+    for author in all_authors {
+        groups
+            .entry(normalize(&author.name, &author.email))
+            .or_insert_with(Vec::new)
+            .push(author);
+    }
+
+    let mut merged = 0;
+    for ((normal_name, normal_email), group) in groups {
+        // the oldest author is kept, and the others merged into it
+        let mut group = group.into_iter();
+        let mut kept = group.next().unwrap();
+
+        for duplicate in group {
+            info!(
+                log,
+                "Merging {} <{}> into {} <{}>", duplicate.name, duplicate.email, kept.name, kept.email
+            );
+            if !dry_run {
+                kept = merge(conn, &duplicate, &kept);
+            }
+            merged += 1;
+        }
+
+        if kept.name != normal_name || kept.email != normal_email {
+            info!(
+                log,
+                "Normalizing {} <{}> to {} <{}>", kept.name, kept.email, normal_name, normal_email
+            );
+            if !dry_run {
+                update(authors.find(kept.id))
+                    .set((name.eq(&normal_name), email.eq(&normal_email)))
+                    .execute(conn)
+                    .expect("Error normalizing author");
+            }
+        }
+    }
+
+    merged
+}
+
+/// the `.mailmap` line that maps `from` to `into`, so the repository can
+/// learn about the merge too
+pub fn mailmap_line(from: &Author, into: &Author) -> String {
+    format!(
+        "{} <{}> {} <{}>",
+        into.name, into.email, from.name, from.email
+    )
+}
+
+#[test]
+fn test_mailmap_line() {
+    let author = |id: i32, name: &str, email: &str| Author {
+        id: id,
+        name: name.to_string(),
+        email: email.to_string(),
+        visible: true,
+        person_id: None,
+        github_login: None,
+        anonymous: false,
+    };
+    let from = author(2, "ana lopez", "ana@old.example.com");
+    let into = author(1, "Ana López", "ana@example.com");
+
+    let line = mailmap_line(&from, &into);
+    assert_eq!(line, "Ana López <ana@example.com> ana lopez <ana@old.example.com>");
+
+    let mailmap = Mailmap::new(&line);
+    assert_eq!(
+        mailmap.map("ana lopez", "ana@old.example.com"),
+        ("Ana López".to_string(), "ana@example.com".to_string())
+    );
+    assert_eq!(
+        mailmap.map("Jo Smith", "jo@example.org"),
+        ("Jo Smith".to_string(), "jo@example.org".to_string())
+    );
+}
+
+#[test]
+fn test_normalize() {
+    let composed = "Jos\u{e9}";
+    let decomposed = "Jose\u{301}";
+
+    assert_eq!(
+        normalize(decomposed, " jose@Example.COM "),
+        (composed.to_string(), "jose@example.com".to_string())
+    );
+    assert_eq!(
+        normalize(composed, "jose@example.com"),
+        normalize(decomposed, "jose@EXAMPLE.com")
+    );
+    assert_eq!(
+        normalize("Jo Smith", "Jo.Smith@example.org").1,
+        "Jo.Smith@example.org"
+    );
+    assert_eq!(normalize("bors", "bors").1, "bors");
+}
+
+#[test]
+fn test_github_login() {
+    assert_eq!(github_login("ana@users.noreply.github.com"), Some("ana"));
+    assert_eq!(
+        github_login("12345+Ana-Lopez@users.noreply.github.com"),
+        Some("Ana-Lopez")
+    );
+    assert_eq!(github_login("ana@example.com"), None);
+    assert_eq!(github_login("noreply@github.com"), None);
+    assert_eq!(github_login("-ana@users.noreply.github.com"), None);
+    assert_eq!(github_login("ana+work@users.noreply.github.com"), None);
+}
@@ -0,0 +1,81 @@
+extern crate thanks;
+
+extern crate clap;
+extern crate diesel;
+
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use clap::{App, Arg, ArgGroup};
+use diesel::prelude::*;
+use slog::DrainExt;
+
+use thanks::models::Author;
+
+fn main() {
+    let matches = App::new("anonymous")
+        .about("count someone's contributions without showing their name")
+        .arg(
+            Arg::with_name("email")
+                .short("e")
+                .long("email")
+                .help("email of the authors to make anonymous")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("person")
+                .long("person")
+                .help("name of a person, making all of their identities anonymous")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("named")
+                .long("named")
+                .help("Use this to show their name again"),
+        )
+        .group(
+            ArgGroup::with_name("who")
+                .args(&["email", "person"])
+                .required(true),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let connection = thanks::establish_connection();
+
+    let identities: Vec<Author> = match matches.value_of("email") {
+        Some(author_email) => {
+            use thanks::schema::authors::dsl::*;
+
+            let author_email = thanks::authors::normalize("", author_email).1;
+            authors
+                .filter(email.eq(&author_email))
+                .load(&connection)
+                .expect("Error loading authors")
+        }
+        None => {
+            let person_name = matches.value_of("person").unwrap();
+            let person = thanks::people::find(&connection, person_name)
+                .expect(&format!("Unable to find person {}", person_name));
+            thanks::people::identities_of(&connection, &person)
+        }
+    };
+
+    if identities.is_empty() {
+        panic!("Unable to find any authors");
+    }
+
+    let anonymous = !matches.is_present("named");
+    for author in &identities {
+        thanks::people::set_anonymous(&connection, author, anonymous);
+        match anonymous {
+            true => info!(log, "{} <{}> is anonymous", author.name, author.email),
+            false => info!(log, "{} <{}> is named again", author.name, author.email),
+        }
+    }
+}
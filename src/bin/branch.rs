@@ -0,0 +1,85 @@
+extern crate thanks;
+
+extern crate clap;
+extern crate diesel;
+
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use diesel::prelude::*;
+use clap::{App, Arg};
+use slog::DrainExt;
+
+fn main() {
+    let matches = App::new("branch")
+        .about("choose the branch a project tracks and what it's shown as")
+        .arg(
+            Arg::with_name("project_name")
+                .short("n")
+                .long("name")
+                .help("name of the project")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("branch")
+                .long("branch")
+                .help("the branch where unreleased commits land")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("title")
+                .long("title")
+                .help("what to show the branch's release as")
+                .takes_value(true)
+                .conflicts_with("no_title"),
+        )
+        .arg(
+            Arg::with_name("no_title")
+                .long("no-title")
+                .help("show the branch's release as the branch's name"),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let project_name = matches.value_of("project_name").unwrap();
+    info!(log, "Project name: {}", project_name);
+
+    use thanks::schema::projects::dsl::{name, projects};
+    use thanks::models::Project;
+
+    let connection = thanks::establish_connection();
+
+    let find_project = || {
+        projects
+            .filter(name.eq(project_name))
+            .first::<Project>(&connection)
+            .expect("Unknown project!")
+    };
+    let project = find_project();
+
+    let changed = matches.is_present("branch") || matches.is_present("title")
+        || matches.is_present("no_title");
+    if changed {
+        let branch = matches.value_of("branch").unwrap_or(&project.branch);
+        let title = if matches.is_present("no_title") {
+            None
+        } else {
+            matches
+                .value_of("title")
+                .or_else(|| project.branch_title.as_ref().map(|t| &t[..]))
+        };
+
+        thanks::projects::set_branch(&connection, &project, branch, title);
+        info!(log, "Tracking {}", branch);
+    }
+
+    let project = find_project();
+    println!("branch: {}", project.branch);
+    println!("shown as: {}", project.release_title(&project.branch));
+}
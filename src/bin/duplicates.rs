@@ -0,0 +1,50 @@
+extern crate thanks;
+
+extern crate clap;
+
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use clap::{App, Arg};
+use slog::DrainExt;
+
+fn main() {
+    let matches = App::new("duplicates")
+        .about("suggest .mailmap lines for authors that look like the same person")
+        .arg(
+            Arg::with_name("min_confidence")
+                .long("min-confidence")
+                .help("leave out suggestions we're less sure of, in percent")
+                .takes_value(true)
+                .default_value("0"),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let min_confidence: u32 = matches
+        .value_of("min_confidence")
+        .unwrap()
+        .parse()
+        .expect("--min-confidence must be a number");
+
+    let connection = thanks::establish_connection();
+
+    let suggestions: Vec<_> = thanks::duplicates::report(&connection)
+        .into_iter()
+        .filter(|s| s.confidence() >= min_confidence)
+        .collect();
+
+    // comments and lines that can go straight into a .mailmap
+    for suggestion in &suggestions {
+        let reasons: Vec<String> = suggestion.reasons.iter().map(|r| r.describe()).collect();
+        println!("# {}%: {}", suggestion.confidence(), reasons.join(", "));
+        println!("{}", suggestion.mailmap_line());
+    }
+
+    info!(log, "Found {} likely duplicates", suggestions.len());
+}
@@ -0,0 +1,97 @@
+extern crate thanks;
+
+extern crate clap;
+extern crate diesel;
+
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use clap::{App, Arg, ArgGroup};
+use diesel::prelude::*;
+use slog::DrainExt;
+
+use thanks::models::Author;
+
+fn main() {
+    let matches = App::new("github-login")
+        .about("link authors to their GitHub profiles")
+        .arg(
+            Arg::with_name("author")
+                .long("author")
+                .help("the author to link, like 'Ana Lopez <ana@example.com>'")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("person")
+                .long("person")
+                .help("the person to link, over the logins of their identities")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("login")
+                .long("login")
+                .help("their GitHub login")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("unset")
+                .long("unset")
+                .help("forget the login instead")
+                .conflicts_with("login"),
+        )
+        .arg(
+            Arg::with_name("from_emails")
+                .long("from-emails")
+                .help("fill in the logins of the authors with users.noreply.github.com emails"),
+        )
+        .group(
+            ArgGroup::with_name("who")
+                .args(&["author", "person", "from_emails"])
+                .required(true),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let connection = thanks::establish_connection();
+
+    if matches.is_present("from_emails") {
+        let filled = thanks::authors::fill_github_logins(&connection);
+        info!(log, "Found the GitHub logins of {} authors", filled);
+        return;
+    }
+
+    let login = match (matches.value_of("login"), matches.is_present("unset")) {
+        (Some(login), _) => Some(login.trim_left_matches('@')),
+        (None, true) => None,
+        (None, false) => panic!("Either --login or --unset is needed"),
+    };
+
+    if let Some(identity) = matches.value_of("author") {
+        use thanks::schema::authors::dsl::*;
+
+        let (author_name, author_email) = thanks::commits::parse_identity(identity)
+            .expect(&format!("{} should look like 'Name <email>'", identity));
+
+        let author = authors
+            .filter(name.eq(&author_name))
+            .filter(email.eq(&author_email))
+            .first::<Author>(&connection)
+            .expect(&format!("Unable to find author {}", identity));
+
+        thanks::authors::set_github_login(&connection, &author, login);
+        info!(log, "{} is now {:?} on GitHub", identity, login);
+    }
+
+    if let Some(person_name) = matches.value_of("person") {
+        let person = thanks::people::find(&connection, person_name)
+            .expect(&format!("Unable to find person {}", person_name));
+
+        thanks::people::set_github_login(&connection, &person, login);
+        info!(log, "{} is now {:?} on GitHub", person_name, login);
+    }
+}
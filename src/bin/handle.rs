@@ -0,0 +1,59 @@
+extern crate thanks;
+
+extern crate clap;
+
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use clap::{App, Arg};
+use slog::DrainExt;
+
+use thanks::authors::AuthorStore;
+use thanks::mailmap::Mailmap;
+
+fn main() {
+    let matches = App::new("handle")
+        .about("say who a reviewer handle belongs to")
+        .arg(
+            Arg::with_name("handle")
+                .long("handle")
+                .help("GitHub login the reviewer is named by, like in bors' r=")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("name")
+                .short("n")
+                .long("name")
+                .help("name of the author")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("email")
+                .short("e")
+                .long("email")
+                .help("email of the author")
+                .takes_value(true)
+                .required(true),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let handle = matches.value_of("handle").unwrap();
+    let name = matches.value_of("name").unwrap();
+    let email = matches.value_of("email").unwrap();
+
+    let connection = thanks::establish_connection();
+
+    // the author as they're already known, so reviews and commits add up
+    let author = AuthorStore::new(&connection, Mailmap::new("")).get(name, email);
+    thanks::reviews::set_handle(&connection, handle, &author);
+
+    info!(log, "{} is now {} <{}>", handle, author.name, author.email);
+}
@@ -0,0 +1,92 @@
+extern crate thanks;
+
+extern crate clap;
+extern crate diesel;
+
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use diesel::prelude::*;
+use clap::{App, Arg};
+use slog::DrainExt;
+
+fn main() {
+    let matches = App::new("map-release")
+        .about("credit a repository's tag to the release that shipped it")
+        .arg(
+            Arg::with_name("project_name")
+                .short("n")
+                .long("name")
+                .help("name of the project")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("repository")
+                .short("r")
+                .long("repository")
+                .help("name of the repository, like cargo")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("tag")
+                .short("t")
+                .long("tag")
+                .help("tag of the repository that shipped")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("version")
+                .short("v")
+                .long("version")
+                .help("release of the project it shipped in")
+                .takes_value(true)
+                .required(true),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let project_name = matches.value_of("project_name").unwrap();
+    info!(log, "Project name: {}", project_name);
+    let repository_name = matches.value_of("repository").unwrap();
+    let tag = matches.value_of("tag").unwrap();
+    info!(log, "Tag: {} {}", repository_name, tag);
+    let release_name = matches.value_of("version").unwrap();
+    info!(log, "Release: {}", release_name);
+
+    use thanks::models::{Project, Release};
+
+    let connection = thanks::establish_connection();
+
+    let project = {
+        use thanks::schema::projects::dsl::*;
+
+        projects
+            .filter(name.eq(project_name))
+            .first::<Project>(&connection)
+            .expect("Unknown project!")
+    };
+
+    let repository = thanks::repositories::find(&connection, &project, repository_name)
+        .expect("Unknown repository! Add it with new-repository first.");
+
+    let release = {
+        use thanks::schema::releases::dsl::*;
+
+        Release::belonging_to(&project)
+            .filter(version.eq(release_name))
+            .first::<Release>(&connection)
+            .expect("Unknown release!")
+    };
+
+    thanks::repositories::import_release(&log, &connection, &repository, &release, tag);
+
+    info!(log, "Done!");
+}
@@ -0,0 +1,78 @@
+extern crate thanks;
+
+extern crate clap;
+extern crate diesel;
+
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use clap::{App, Arg};
+use diesel::prelude::*;
+use slog::DrainExt;
+
+use thanks::models::Author;
+
+fn main() {
+    let matches = App::new("merge-authors")
+        .about("merge an author that is a duplicate of another one")
+        .arg(
+            Arg::with_name("from")
+                .long("from")
+                .help("the duplicate, like 'ana lopez <ana@old.example.com>'")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("into")
+                .long("into")
+                .help("the author to keep, like 'Ana Lopez <ana@example.com>'")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("mailmap")
+                .long("mailmap")
+                .help("print the .mailmap line for the merge"),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let connection = thanks::establish_connection();
+
+    let find_author = |identity: &str| {
+        use thanks::schema::authors::dsl::*;
+
+        let (author_name, author_email) = thanks::commits::parse_identity(identity)
+            .expect(&format!("{} should look like 'Name <email>'", identity));
+
+        authors
+            .filter(name.eq(&author_name))
+            .filter(email.eq(&author_email))
+            .first::<Author>(&connection)
+            .expect(&format!("Unable to find author {}", identity))
+    };
+
+    let from = find_author(matches.value_of("from").unwrap());
+    let into = find_author(matches.value_of("into").unwrap());
+    if from.id == into.id {
+        panic!("Can't merge an author into itself");
+    }
+
+    let merged = thanks::authors::merge(&connection, &from, &into);
+    info!(
+        log,
+        "Merged {} <{}> into {} <{}>", from.name, from.email, merged.name, merged.email
+    );
+    if !merged.visible {
+        info!(log, "{} <{}> stays opted out", merged.name, merged.email);
+    }
+
+    if matches.is_present("mailmap") {
+        println!("{}", thanks::authors::mailmap_line(&from, &merged));
+    }
+}
@@ -0,0 +1,112 @@
+extern crate thanks;
+
+extern crate clap;
+extern crate diesel;
+
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use diesel::prelude::*;
+use clap::{App, Arg, ArgGroup};
+use slog::DrainExt;
+
+use thanks::models::Author;
+
+fn main() {
+    let matches = App::new("name-override")
+        .about("show a contributor who changed their name by their new one everywhere")
+        .arg(
+            Arg::with_name("email")
+                .long("email")
+                .help("the email of the author, which covers every name they used with it")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("id")
+                .long("id")
+                .help("the id of the author")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("to")
+                .long("to")
+                .help("the name to show instead")
+                .takes_value(true)
+                .conflicts_with("remove"),
+        )
+        .arg(
+            Arg::with_name("remove")
+                .long("remove")
+                .help("show the author by their own name again"),
+        )
+        .arg(
+            Arg::with_name("list")
+                .long("list")
+                .help("show the overrides")
+                .conflicts_with_all(&["email", "id"]),
+        )
+        .group(ArgGroup::with_name("author").args(&["email", "id"]))
+        .group(
+            ArgGroup::with_name("action")
+                .args(&["to", "remove", "list"])
+                .required(true),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let connection = thanks::establish_connection();
+
+    if matches.is_present("list") {
+        for (name_override, author) in thanks::people::overrides_with_authors(&connection) {
+            println!(
+                "{} {} <{}> -> {}",
+                author.id,
+                author.name,
+                author.email,
+                name_override.display_name
+            );
+        }
+        return;
+    }
+
+    let found: Vec<Author> = {
+        use thanks::schema::authors::dsl::*;
+
+        if let Some(author_email) = matches.value_of("email") {
+            let author_email = thanks::authors::normalize("", author_email).1;
+            authors
+                .filter(email.eq(author_email))
+                .load(&connection)
+                .expect("Error loading authors")
+        } else if let Some(author_id) = matches.value_of("id") {
+            let author_id: i32 = author_id.parse().expect("--id should be a number");
+            authors
+                .find(author_id)
+                .load(&connection)
+                .expect("Error loading authors")
+        } else {
+            panic!("--email or --id is needed");
+        }
+    };
+    if found.is_empty() {
+        panic!("Unable to find the author");
+    }
+
+    for author in found {
+        if matches.is_present("remove") {
+            match thanks::people::remove_override(&connection, &author) {
+                true => info!(log, "{} is shown by their own name again", author.name),
+                false => error!(log, "{} <{}> wasn't overridden", author.name, author.email),
+            }
+        } else {
+            let new_name = matches.value_of("to").unwrap();
+            thanks::people::set_override(&connection, &author, new_name);
+            info!(log, "{} is shown as {} from now on", author.name, new_name);
+        }
+    }
+}
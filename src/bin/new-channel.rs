@@ -0,0 +1,121 @@
+extern crate thanks;
+
+extern crate clap;
+extern crate diesel;
+
+extern crate git2;
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use diesel::prelude::*;
+use clap::{App, Arg};
+use slog::DrainExt;
+use git2::Repository;
+
+fn main() {
+    let matches = App::new("new-channel")
+        .about("create a pseudo-release that follows a branch")
+        .arg(
+            Arg::with_name("filepath")
+                .short("p")
+                .long("path")
+                .help("filepath of the source code")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("project_name")
+                .short("n")
+                .long("name")
+                .help("name of the project")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("channel")
+                .short("c")
+                .long("channel")
+                .help("name of the channel, like beta or nightly")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("branch")
+                .short("b")
+                .long("branch")
+                .help("branch the channel follows")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("since")
+                .short("s")
+                .long("since")
+                .help("revision the channel starts from (defaults to the latest release)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("link")
+                .short("l")
+                .long("link")
+                .help("link to the channel's commits")
+                .takes_value(true)
+                .required(true),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let project_name = matches.value_of("project_name").unwrap();
+    info!(log, "Project name: {}", project_name);
+    let channel_name = matches.value_of("channel").unwrap();
+    info!(log, "Channel name: {}", channel_name);
+    let branch = matches.value_of("branch").unwrap();
+    info!(log, "Branch: {}", branch);
+    let since = matches.value_of("since");
+    let path = matches.value_of("filepath").unwrap();
+    info!(log, "Path to {} repo: {}", project_name, path);
+    let link = matches.value_of("link").unwrap();
+
+    use thanks::schema::projects::dsl::{name, projects};
+    use thanks::models::{Project, Release};
+    use thanks::authors::AuthorStore;
+
+    let connection = thanks::establish_connection();
+
+    let project = projects
+        .filter(name.eq(project_name))
+        .first::<Project>(&connection)
+        .expect("Unknown project!");
+
+    {
+        use thanks::schema::releases::dsl::version;
+
+        if Release::belonging_to(&project)
+            .filter(version.eq(channel_name))
+            .first::<Release>(&connection)
+            .is_ok()
+        {
+            panic!("Release {} already exists! Pick another name.", channel_name);
+        }
+    }
+
+    let repo = Repository::open(path).unwrap();
+
+    let channel = thanks::channels::create(
+        &connection,
+        project.id,
+        channel_name,
+        branch,
+        since,
+        link,
+    );
+    info!(log, "Created channel {}", channel.name);
+
+    let mut cache = AuthorStore::from_file(&connection, path);
+    thanks::channels::sync(&log, &connection, &repo, &mut cache, &channel);
+}
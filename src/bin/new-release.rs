@@ -82,10 +82,8 @@ fn main() {
         .filter(name.eq(project_name))
         .first::<Project>(&connection)
         .expect("Unknown project!");
-    let release = Release::belonging_to(&project)
-        .order(id.desc())
-        .first::<Release>(&connection)
-        .unwrap();
+    let release = thanks::releases::latest(&connection, project.id)
+        .expect("No previous release found!");
 
     info!(log, "Previous release: {}", release.version);
     info!(log, "Creating new release: {}", new_release_name);
@@ -109,6 +107,7 @@ fn main() {
         &changelog_link,
     );
     info!(log, "Created release {}", new_release.version);
+    thanks::releases::record_date(&connection, &repo, &new_release);
 
     info!(log, "Assigning commits for {}", new_release.version);
     let mut cache = AuthorStore::from_file(&connection, path);
@@ -120,4 +119,17 @@ fn main() {
         thanks::releases::get_commits(&repo, &new_release.version, &release.version),
         project.id,
     );
+    thanks::submodules::import(
+        &log,
+        &connection,
+        &repo,
+        &project,
+        &new_release.version,
+        Some(&release.version),
+    );
+
+    // the channels now start from the new release
+    for channel in thanks::channels::for_project(&connection, project.id) {
+        thanks::channels::sync(&log, &connection, &repo, &mut cache, &channel);
+    }
 }
@@ -0,0 +1,96 @@
+extern crate thanks;
+
+extern crate clap;
+extern crate diesel;
+
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use diesel::prelude::*;
+use clap::{App, Arg};
+use slog::DrainExt;
+
+use std::fs;
+
+fn main() {
+    let matches = App::new("new-repository")
+        .about("add another repository that ships with a project's releases")
+        .arg(
+            Arg::with_name("filepath")
+                .short("p")
+                .long("path")
+                .help("filepath of the repository's source code")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("project_name")
+                .short("n")
+                .long("name")
+                .help("name of the project")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("repository")
+                .short("r")
+                .long("repository")
+                .help("name of the repository, like cargo")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("github_name")
+                .short("g")
+                .long("github")
+                .help("name of the repository on GitHub, like rust-lang/cargo")
+                .takes_value(true)
+                .required(true),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let project_name = matches.value_of("project_name").unwrap();
+    info!(log, "Project name: {}", project_name);
+    let repository_name = matches.value_of("repository").unwrap();
+    info!(log, "Repository name: {}", repository_name);
+    let github_name = matches.value_of("github_name").unwrap();
+    let path = matches.value_of("filepath").unwrap();
+    info!(log, "Path to {} repo: {}", repository_name, path);
+
+    // the path is kept, so make sure it still works from anywhere
+    let repo_path = fs::canonicalize(path).expect("Could not find the repository");
+    let repo_path = repo_path.to_str().unwrap();
+
+    use thanks::schema::projects::dsl::{name, projects};
+    use thanks::models::Project;
+
+    let connection = thanks::establish_connection();
+
+    let project = projects
+        .filter(name.eq(project_name))
+        .first::<Project>(&connection)
+        .expect("Unknown project!");
+
+    if thanks::repositories::find(&connection, &project, repository_name).is_some() {
+        panic!(
+            "Repository {} already exists! Pick another name.",
+            repository_name
+        );
+    }
+
+    let repository = thanks::repositories::create(
+        &connection,
+        project.id,
+        repository_name,
+        github_name,
+        repo_path,
+        None,
+    );
+    info!(log, "Created repository {}", repository.name);
+}
@@ -0,0 +1,33 @@
+extern crate thanks;
+
+extern crate clap;
+
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use clap::{App, Arg};
+use slog::DrainExt;
+
+fn main() {
+    let matches = App::new("normalize-authors")
+        .about("merge authors whose names or emails only differ in how they're written")
+        .arg(
+            Arg::with_name("dry_run")
+                .long("dry-run")
+                .help("only show what would change"),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let connection = thanks::establish_connection();
+
+    let dry_run = matches.is_present("dry_run");
+    let merged = thanks::authors::normalize_existing(&log, &connection, dry_run);
+
+    info!(log, "Merged {} authors", merged);
+}
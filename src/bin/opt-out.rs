@@ -2,31 +2,62 @@ extern crate thanks;
 
 extern crate clap;
 
-extern crate diesel;
-
 #[macro_use]
 extern crate slog;
 extern crate slog_term;
 
-use clap::{App, Arg};
+use clap::{App, Arg, ArgGroup};
 use slog::DrainExt;
 
-use diesel::prelude::*;
+use thanks::opt_outs::Rule;
 
 fn main() {
     let matches = App::new("opt-out")
-        .about("mark an author as opted-out")
+        .about("keep someone off the pages, including under identities they use later")
         .arg(
             Arg::with_name("email")
                 .short("e")
                 .long("email")
-                .takes_value(true)
-                .required(true),
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("email_pattern")
+                .long("email-pattern")
+                .help("regular expression for the emails, like '@example\\.com$'")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("name")
+                .short("n")
+                .long("name")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("person")
+                .long("person")
+                .help("name of a person, covering all of their identities")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("reason")
+                .long("reason")
+                .help("why, for whoever looks at the rule later")
+                .takes_value(true),
         )
         .arg(
             Arg::with_name("opt-in")
                 .long("opt-in")
-                .help("Use this to mark author as opted-in again"),
+                .help("Use this to remove the rule and show the authors it covered again"),
+        )
+        .arg(
+            Arg::with_name("list")
+                .long("list")
+                .help("show the rules in force"),
+        )
+        .group(
+            ArgGroup::with_name("rule")
+                .args(&["email", "email_pattern", "name", "person", "list"])
+                .required(true),
         )
         .get_matches();
 
@@ -35,29 +66,43 @@ fn main() {
         o!("version" => env!("CARGO_PKG_VERSION")),
     );
 
-    let visible = matches.is_present("opt-in");
+    let connection = thanks::establish_connection();
+
+    if matches.is_present("list") {
+        for opt_out in thanks::opt_outs::all(&connection) {
+            let who = opt_out
+                .email
+                .map(|email| format!("email {}", email))
+                .or(opt_out.email_pattern.map(|p| format!("email pattern {}", p)))
+                .or(opt_out.name.map(|name| format!("name {}", name)))
+                .or(opt_out.person_id.map(|id| format!("person #{}", id)))
+                .unwrap_or_default();
+            println!("{} ({}, since {})", who, opt_out.reason, opt_out.created_at);
+        }
+        return;
+    }
 
-    match matches.value_of("email") {
-        Some(email) => opt_out(&log, email, visible),
-        None => error!(log, "No email specified"),
+    let rule = if let Some(email) = matches.value_of("email") {
+        Rule::Email(email.to_string())
+    } else if let Some(pattern) = matches.value_of("email_pattern") {
+        Rule::EmailPattern(pattern.to_string())
+    } else if let Some(name) = matches.value_of("name") {
+        Rule::Name(name.to_string())
+    } else {
+        let person_name = matches.value_of("person").unwrap();
+        let person = thanks::people::find(&connection, person_name)
+            .expect(&format!("Unable to find person {}", person_name));
+        Rule::Person(person.id)
     };
-}
-
-fn opt_out(log: &slog::Logger, author_email: &str, new_visible: bool) {
-    use thanks::schema::authors::dsl::*;
-    use thanks::models::Author;
-    let connection = thanks::establish_connection();
 
-    diesel::update(authors.filter(email.eq(author_email)))
-        .set(visible.eq(new_visible))
-        .get_result::<Author>(&connection)
-        .expect(&format!(
-            "Unable to find author with email {}",
-            author_email
-        ));
-
-    match new_visible {
-        true => info!(log, "Opted-in author with email: {}", author_email),
-        false => info!(log, "Opted-out author with email: {}", author_email),
+    if matches.is_present("opt-in") {
+        match thanks::opt_outs::remove(&connection, &rule) {
+            0 => error!(log, "No opt-out for {:?}", rule),
+            _ => info!(log, "Opted-in {:?}", rule),
+        }
+    } else {
+        let reason = matches.value_of("reason").unwrap_or("");
+        thanks::opt_outs::add(&connection, &rule, reason);
+        info!(log, "Opted-out {:?}", rule);
     }
 }
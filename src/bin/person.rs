@@ -0,0 +1,102 @@
+extern crate thanks;
+
+extern crate clap;
+extern crate diesel;
+
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use clap::{App, Arg};
+use diesel::prelude::*;
+use slog::DrainExt;
+
+use thanks::models::Author;
+
+fn main() {
+    let matches = App::new("person")
+        .about("group the identities someone committed under")
+        .arg(
+            Arg::with_name("person")
+                .long("person")
+                .help("name of the person, created if it doesn't exist yet")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("attach")
+                .long("attach")
+                .help("an identity of the person, like 'ana <ana@old.example.com>'")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("detach")
+                .long("detach")
+                .help("an identity that isn't the person after all")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("rename")
+                .long("rename")
+                .help("the name to show the person with from now on")
+                .takes_value(true),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let connection = thanks::establish_connection();
+
+    let find_author = |identity: &str| {
+        use thanks::schema::authors::dsl::*;
+
+        let (author_name, author_email) = thanks::commits::parse_identity(identity)
+            .expect(&format!("{} should look like 'Name <email>'", identity));
+
+        authors
+            .filter(name.eq(&author_name))
+            .filter(email.eq(&author_email))
+            .first::<Author>(&connection)
+            .expect(&format!("Unable to find author {}", identity))
+    };
+
+    let person_name = matches.value_of("person").unwrap();
+    let mut person = match thanks::people::find(&connection, person_name) {
+        Some(person) => person,
+        None => {
+            info!(log, "Creating person {}", person_name);
+            thanks::people::create(&connection, person_name)
+        }
+    };
+
+    for identity in matches.values_of("attach").into_iter().flat_map(|v| v) {
+        let author = find_author(identity);
+        thanks::people::attach(&connection, &person, &author);
+        info!(log, "Attached {} to {}", identity, person.name);
+    }
+
+    for identity in matches.values_of("detach").into_iter().flat_map(|v| v) {
+        let author = find_author(identity);
+        if author.person_id != Some(person.id) {
+            panic!("{} isn't an identity of {}", identity, person.name);
+        }
+        thanks::people::detach(&connection, &author);
+        info!(log, "Detached {} from {}", identity, person.name);
+    }
+
+    if let Some(new_name) = matches.value_of("rename") {
+        person = thanks::people::rename(&connection, &person, new_name);
+        info!(log, "Renamed {} to {}", person_name, person.name);
+    }
+
+    for author in thanks::people::identities_of(&connection, &person) {
+        println!("{} <{}>", author.name, author.email);
+    }
+}
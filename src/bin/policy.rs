@@ -0,0 +1,137 @@
+extern crate thanks;
+
+extern crate clap;
+extern crate diesel;
+
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use diesel::prelude::*;
+use clap::{App, Arg, ArgGroup};
+use slog::DrainExt;
+
+fn main() {
+    let matches = App::new("policy")
+        .about("choose which commits of a project go uncredited")
+        .arg(
+            Arg::with_name("project_name")
+                .short("n")
+                .long("name")
+                .help("name of the project")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("skip_merges")
+                .long("skip-merges")
+                .help("don't credit merge commits"),
+        )
+        .arg(
+            Arg::with_name("keep_merges")
+                .long("keep-merges")
+                .help("credit merge commits"),
+        )
+        .group(ArgGroup::with_name("merges").args(&["skip_merges", "keep_merges"]))
+        .arg(
+            Arg::with_name("bot_name")
+                .long("bot-name")
+                .help("regular expression for the name of a bot")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("bot_email")
+                .long("bot-email")
+                .help("regular expression for the email of a bot")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ignore_path")
+                .long("ignore-path")
+                .help("glob of vendored or generated paths to leave out of line counts")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("remove")
+                .long("remove")
+                .help("remove the bot or ignored path instead of adding it"),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let project_name = matches.value_of("project_name").unwrap();
+    info!(log, "Project name: {}", project_name);
+
+    use thanks::schema::projects::dsl::{name, projects};
+    use thanks::models::Project;
+
+    let connection = thanks::establish_connection();
+
+    let find_project = || {
+        projects
+            .filter(name.eq(project_name))
+            .first::<Project>(&connection)
+            .expect("Unknown project!")
+    };
+    let project = find_project();
+
+    if matches.is_present("skip_merges") {
+        thanks::policies::set_skip_merges(&connection, &project, true);
+    }
+    if matches.is_present("keep_merges") {
+        thanks::policies::set_skip_merges(&connection, &project, false);
+    }
+
+    let bot_name = matches.value_of("bot_name");
+    let bot_email = matches.value_of("bot_email");
+    let policy_changed =
+        matches.is_present("merges") || bot_name.is_some() || bot_email.is_some();
+    if bot_name.is_some() || bot_email.is_some() {
+        if matches.is_present("remove") {
+            let removed = thanks::policies::remove_bot(&connection, project.id, bot_name, bot_email);
+            info!(log, "Removed {} bots", removed);
+        } else {
+            let bot = thanks::policies::add_bot(&connection, project.id, bot_name, bot_email);
+            info!(log, "Added bot {}", bot.id);
+        }
+    }
+
+    let ignore_path = matches.value_of("ignore_path");
+    if let Some(glob) = ignore_path {
+        if matches.is_present("remove") {
+            let removed = thanks::stats::remove_ignored_path(&connection, &project, glob);
+            info!(log, "Removed {} ignored paths", removed);
+        } else {
+            thanks::stats::add_ignored_path(&connection, &project, glob);
+            info!(log, "Ignoring {}", glob);
+        }
+    }
+
+    let project = find_project();
+    println!("skip merges: {}", project.skip_merges);
+    for bot in thanks::policies::bots_of(&connection, &project) {
+        println!(
+            "bot: name {} email {}",
+            bot.name_pattern.unwrap_or_else(|| "*".to_string()),
+            bot.email_pattern.unwrap_or_else(|| "*".to_string())
+        );
+    }
+
+    for path in thanks::stats::ignored_paths_of(&connection, &project) {
+        println!("ignored path: {}", path.glob);
+    }
+
+    // showing the policy doesn't touch any commits
+    if policy_changed {
+        thanks::policies::apply(&log, &connection, &project);
+    }
+
+    // counting lines means diffing every commit, so only do it when needed
+    if ignore_path.is_some() {
+        thanks::stats::apply(&log, &connection, &project);
+    }
+}
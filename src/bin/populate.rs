@@ -26,6 +26,10 @@ use slog::DrainExt;
 
 use git2::Repository;
 
+use std::fs;
+
+use thanks::versions::VersionScheme;
+
 fn main() {
     let matches = App::new("populate")
         .about("initialize the database")
@@ -61,6 +65,26 @@ fn main() {
                 .takes_value(true)
                 .required(true),
         )
+        .arg(
+            Arg::with_name("version_scheme")
+                .long("version-scheme")
+                .help("how the project's versions are ordered")
+                .takes_value(true)
+                .possible_values(VersionScheme::names())
+                .default_value("lenient-semver"),
+        )
+        .arg(
+            Arg::with_name("submodules")
+                .long("submodules")
+                .help("also credit the commits that submodules moved by"),
+        )
+        .arg(
+            Arg::with_name("branch")
+                .long("branch")
+                .help("the branch where unreleased commits land")
+                .takes_value(true)
+                .default_value("master"),
+        )
         .get_matches();
 
     let log = slog::Logger::root(
@@ -113,8 +137,32 @@ fn main() {
     let github_name = matches.value_of("github_name").unwrap();
     info!(log, "GitHub name: {}", github_name);
 
+    // get version scheme
+    let version_scheme = matches.value_of("version_scheme").unwrap();
+    info!(log, "Version scheme: {}", version_scheme);
+    let version_scheme = VersionScheme::from_name(version_scheme).unwrap();
+
+    let follow_submodules = matches.is_present("submodules");
+    info!(log, "Follow submodules: {}", follow_submodules);
+
+    let branch = matches.value_of("branch").unwrap();
+    info!(log, "Branch: {}", branch);
+
+    // remember where the repo is, so that update-commit-db can sync from it
+    let repo_path = fs::canonicalize(path).unwrap();
+    let repo_path = repo_path.to_str().unwrap();
+
     // create project
-    let project = thanks::projects::create(&connection, project_name, url_path, github_name);
+    let project = thanks::projects::create(
+        &connection,
+        project_name,
+        url_path,
+        github_name,
+        version_scheme,
+        Some(repo_path),
+        follow_submodules,
+        branch,
+    );
 
     // Create releases
     let releases = [
@@ -158,27 +206,30 @@ fn main() {
         ("1.16.0", "1.15.0", changelog_link("1.16.0")),
     ];
 
+    let repo = Repository::open(path).unwrap();
+
     // create 0.1, which isn't in the loop because it will have everything assigned
     // to it by default
-    thanks::releases::create(&connection, "0.1", project.id, true, changelog_link("0.1"));
-
-    for &(release, _, link) in releases.iter() {
-        thanks::releases::create(&connection, release, project.id, true, link);
-    }
-
-    // And create the release for all commits that are not released yet
-    thanks::releases::create(
+    let first = thanks::releases::create(
         &connection,
-        "master",
+        "0.1",
         project.id,
         true,
-        changelog_link("master"),
+        changelog_link("0.1"),
     );
+    thanks::releases::record_date(&connection, &repo, &first);
 
-    let repo = Repository::open(path).unwrap();
+    for &(release, _, link) in releases.iter() {
+        let release = thanks::releases::create(&connection, release, project.id, true, link);
+        thanks::releases::record_date(&connection, &repo, &release);
+    }
+
+    // And create the release for all commits that are not released yet
+    let branch_link = format!("https://github.com/{}/commits/{}", github_name, branch);
+    thanks::releases::create_pseudo(&connection, branch, project.id, &branch_link);
 
     let mut lookup = thanks::authors::AuthorStore::from_file(&connection, path);
-    lookup.warm_cache(&repo);
+    lookup.warm_cache(&repo, branch);
 
     // assign first release
     thanks::releases::assign_commits(
@@ -189,6 +240,7 @@ fn main() {
         thanks::releases::get_first_commits(&repo, "0.1"),
         project.id,
     );
+    thanks::submodules::import(&log, &connection, &repo, &project, "0.1", None);
 
     // assign commits to their release
     for &(release, previous, _) in releases.iter() {
@@ -200,25 +252,28 @@ fn main() {
             thanks::releases::get_commits(&repo, release, previous),
             project.id,
         );
+        thanks::submodules::import(&log, &connection, &repo, &project, release, Some(previous));
     }
 
-    // assign master
+    // assign what's on the branch since
     let last = releases.last().unwrap().0;
     thanks::releases::assign_commits(
         &log,
         &repo,
         &mut lookup,
-        "master",
-        thanks::releases::get_commits(&repo, "master", last),
+        branch,
+        thanks::releases::get_commits(&repo, branch, last),
         project.id,
     );
 
+    let tip = repo.revparse_single(branch).unwrap().id();
+    thanks::projects::set_last_synced(&connection, &project, &format!("{}", tip));
+
     info!(log, "Done!");
 }
 
 fn changelog_link(version: &str) -> &str {
     match version {
-        "master"        => "https://github.com/rust-lang/rust/commits/master",
         "0.1"           => "https://github.com/rust-lang/rust/blob/master/RELEASES.md#version-01--2012-01-20",
         "0.2"           => "https://github.com/rust-lang/rust/blob/master/RELEASES.md#version-02--2012-03-29",
         "0.3"           => "https://github.com/rust-lang/rust/blob/master/RELEASES.md#version-03--2012-07-12",
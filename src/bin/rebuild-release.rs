@@ -0,0 +1,310 @@
+extern crate thanks;
+
+extern crate clap;
+extern crate diesel;
+
+extern crate git2;
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use clap::{App, Arg};
+use slog::DrainExt;
+use git2::{Oid, Repository};
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use thanks::authors::AuthorStore;
+use thanks::models::{Project, Release};
+use thanks::people::People;
+use thanks::policies::Policy;
+
+fn main() {
+    let matches = App::new("rebuild-release")
+        .about("recompute the commits of a single release")
+        .arg(
+            Arg::with_name("filepath")
+                .short("p")
+                .long("path")
+                .help("filepath of the source code")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("project_name")
+                .short("n")
+                .long("name")
+                .help("name of the project")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("version")
+                .short("v")
+                .long("version")
+                .help("version of the release to rebuild")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("dry_run")
+                .long("dry-run")
+                .help("only show what would change"),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let project_name = matches.value_of("project_name").unwrap();
+    info!(log, "Project name: {}", project_name);
+    let release_name = matches.value_of("version").unwrap();
+    info!(log, "Release: {}", release_name);
+    let path = matches.value_of("filepath").unwrap();
+    info!(log, "Path to {} repo: {}", project_name, path);
+    let dry_run = matches.is_present("dry_run");
+
+    let connection = thanks::establish_connection();
+    let repo = Repository::open(path).unwrap();
+
+    let project = {
+        use thanks::schema::projects::dsl::*;
+
+        projects
+            .filter(name.eq(project_name))
+            .first::<Project>(&connection)
+            .expect("Unknown project!")
+    };
+
+    let releases = thanks::releases::in_order(&connection, project.id);
+    let position = match releases.iter().position(|r| r.version == release_name) {
+        Some(i) => i,
+        None => panic!(
+            "{} is not a release of {}; channels are rebuilt by update-channels",
+            release_name,
+            project_name
+        ),
+    };
+    let release = &releases[position];
+
+    let previous = match position {
+        0 => None,
+        _ => Some(&releases[position - 1].version),
+    };
+    let ids = match previous {
+        None => thanks::releases::get_first_commits(&repo, &release.version),
+        Some(previous) => {
+            info!(log, "Previous release: {}", previous);
+            thanks::releases::get_commits(&repo, &release.version, previous)
+        }
+    };
+
+    let wanted: HashSet<String> = ids.iter().map(|id| format!("{}", id)).collect();
+    let current: HashSet<String> = commits_of(&connection, release).into_iter().collect();
+
+    let incoming: Vec<&String> = wanted.difference(&current).collect();
+    let displaced: Vec<&String> = current.difference(&wanted).collect();
+
+    info!(
+        log,
+        "{} commits to add, {} commits to move out", incoming.len(), displaced.len()
+    );
+
+    let mut cache = AuthorStore::from_file(&connection, path);
+    let policy = Policy::for_project(&connection, project.id);
+
+    // work out the contributors before touching anything, so that a dry run
+    // shows the same thing a real one would do; the authors of new commits
+    // are found or created to know how they're shown, which a dry run takes
+    // back
+    let mut changes = None;
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        let before = names(&connection, contributor_ids(&connection, &current));
+        let mut ids = contributor_ids(&connection, &wanted);
+        for sha in &incoming {
+            if !is_known(&connection, sha) {
+                ids.extend(new_contributor_ids(&repo, &mut cache, &policy, sha));
+            }
+        }
+        changes = Some((before, names(&connection, ids)));
+
+        match dry_run {
+            true => Err(diesel::result::Error::RollbackTransaction),
+            false => Ok(()),
+        }
+    });
+    match result {
+        Ok(()) | Err(diesel::result::Error::RollbackTransaction) => {}
+        Err(e) => panic!("Error finding the contributors: {}", e),
+    }
+    let (before, after) = changes.unwrap();
+
+    for name in before.difference(&after) {
+        info!(log, "Contributor removed: {}", name);
+    }
+    for name in after.difference(&before) {
+        info!(log, "Contributor added: {}", name);
+    }
+
+    // find where each displaced commit belongs: the release whose range,
+    // from the previous tag to its own, contains it, or the project's branch
+    // if none does
+    let mut homes: HashMap<String, i32> = HashMap::new();
+    let branch = branch_release(&connection, &project);
+    for sha in &displaced {
+        let id = Oid::from_str(sha).unwrap();
+        let home = releases
+            .iter()
+            .enumerate()
+            .find(|&(i, r)| {
+                contains(&repo, &r.version, id)
+                    && (i == 0 || !contains(&repo, &releases[i - 1].version, id))
+            })
+            .map(|(_, r)| r)
+            .unwrap_or(&branch);
+
+        info!(log, "Moving {} to {}", sha, home.version);
+        homes.insert(sha.to_string(), home.id);
+    }
+
+    if dry_run {
+        info!(log, "Dry run, not changing anything");
+        return;
+    }
+
+    connection
+        .transaction::<_, diesel::result::Error, _>(|| {
+            use thanks::schema::commits::dsl::*;
+
+            for (commit_sha, home) in homes {
+                diesel::update(commits.find(commit_sha))
+                    .set(release_id.eq(home))
+                    .execute(&connection)?;
+            }
+
+            thanks::releases::assign_commits(
+                &log,
+                &repo,
+                &mut cache,
+                &release.version,
+                ids,
+                project.id,
+            );
+            thanks::submodules::import(
+                &log,
+                &connection,
+                &repo,
+                &project,
+                &release.version,
+                previous.map(|p| p.as_str()),
+            );
+            Ok(())
+        })
+        .expect("Error rebuilding release");
+
+    info!(log, "Done!");
+}
+
+/// the commits of the release that came from the project's own repository
+fn commits_of(conn: &PgConnection, release: &Release) -> Vec<String> {
+    use thanks::schema::commits::dsl::*;
+
+    commits
+        .filter(release_id.eq(release.id))
+        .filter(repository_id.is_null())
+        .select(sha)
+        .load(conn)
+        .expect("Error loading commits")
+}
+
+fn is_known(conn: &PgConnection, commit_sha: &str) -> bool {
+    use thanks::schema::commits::dsl::*;
+
+    commits
+        .find(commit_sha)
+        .select(sha)
+        .first::<String>(conn)
+        .is_ok()
+}
+
+/// the authors and co-authors of the given commits that are already in the
+/// database, leaving out the ones the policy excluded
+fn contributor_ids(conn: &PgConnection, shas: &HashSet<String>) -> Vec<i32> {
+    use thanks::schema::{co_authors, commits};
+    use diesel::expression::dsl::any;
+
+    let shas: Vec<&String> = shas.iter().collect();
+
+    let mut author_ids: Vec<i32> = commits::table
+        .filter(commits::sha.eq(any(shas.clone())))
+        .filter(commits::excluded.eq(false))
+        .select(commits::author_id)
+        .distinct()
+        .load(conn)
+        .expect("Error loading contributors");
+    author_ids.extend(
+        co_authors::table
+            .inner_join(commits::table)
+            .filter(commits::sha.eq(any(shas)))
+            .filter(commits::excluded.eq(false))
+            .select(co_authors::author_id)
+            .distinct()
+            .load::<i32>(conn)
+            .expect("Error loading co-authors"),
+    );
+    author_ids
+}
+
+/// the authors and co-authors a commit that isn't in the database yet will be
+/// credited to, unless the policy excludes it, like `assign_commits` does
+fn new_contributor_ids(
+    repo: &Repository,
+    cache: &mut AuthorStore,
+    policy: &Policy,
+    sha: &str,
+) -> Vec<i32> {
+    let commit = repo.find_commit(Oid::from_str(sha).unwrap()).unwrap();
+    let author = commit.author();
+    let (name, email) = cache
+        .get_mailmap()
+        .map(author.name().unwrap(), author.email().unwrap());
+    if policy.excludes(commit.parent_count() > 1, &name, &email) {
+        return Vec::new();
+    }
+
+    let mut ids = vec![cache.get(&name, &email).id];
+    for (co_name, co_email) in thanks::commits::co_authors(commit.message().unwrap_or("")) {
+        let (co_name, co_email) = cache.get_mailmap().map(&co_name, &co_email);
+        ids.push(cache.get(&co_name, &co_email).id);
+    }
+    ids
+}
+
+/// the names of the visible people among the authors, as the release page
+/// shows them
+fn names(conn: &PgConnection, author_ids: Vec<i32>) -> BTreeSet<String> {
+    People::load(conn).names(author_ids).into_iter().collect()
+}
+
+fn branch_release(conn: &PgConnection, project: &Project) -> Release {
+    use thanks::schema::releases::dsl::*;
+
+    Release::belonging_to(project)
+        .filter(version.eq(&project.branch))
+        .first(conn)
+        .expect(&format!("could not find {} release", project.branch))
+}
+
+/// whether the tag of a release contains the given commit
+fn contains(repo: &Repository, tag: &str, commit: Oid) -> bool {
+    let tagged = match repo.revparse_single(tag) {
+        Ok(object) => object.peel(git2::ObjectType::Commit).unwrap().id(),
+        Err(_) => return false,
+    };
+
+    tagged == commit || repo.graph_descendant_of(tagged, commit).unwrap_or(false)
+}
@@ -0,0 +1,93 @@
+extern crate thanks;
+
+extern crate clap;
+extern crate diesel;
+
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use diesel::prelude::*;
+use clap::{App, Arg};
+use slog::DrainExt;
+
+fn main() {
+    let matches = App::new("sub-project")
+        .about("thank the people who worked on part of a project")
+        .arg(
+            Arg::with_name("project_name")
+                .short("n")
+                .long("name")
+                .help("name of the parent project")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("sub_project")
+                .long("sub-project")
+                .help("name of the sub-project, like rustdoc")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("glob")
+                .long("glob")
+                .help("paths of the sub-project, like src/librustdoc; replaces the current ones")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let project_name = matches.value_of("project_name").unwrap();
+    let sub_project_name = matches.value_of("sub_project").unwrap();
+    info!(log, "Project name: {}", project_name);
+
+    use thanks::schema::projects::dsl::{name, projects};
+    use thanks::models::Project;
+
+    let connection = thanks::establish_connection();
+
+    let find_project = |project_name: &str| {
+        projects
+            .filter(name.eq(project_name))
+            .first::<Project>(&connection)
+            .ok()
+    };
+
+    let parent = find_project(project_name).expect("Unknown project!");
+    // commits synced from the GitHub API don't say which paths they touch
+    if parent.repo_path.is_none() {
+        panic!(
+            "{} has no local clone, which sub-projects need to find their commits",
+            project_name
+        );
+    }
+    let sub_project = match find_project(sub_project_name) {
+        Some(sub_project) => {
+            if sub_project.parent_id != Some(parent.id) {
+                panic!("{} isn't a sub-project of {}", sub_project_name, project_name);
+            }
+            sub_project
+        }
+        None => {
+            info!(log, "Creating sub-project {}", sub_project_name);
+            thanks::scopes::create(&connection, &parent, sub_project_name)
+        }
+    };
+
+    if let Some(globs) = matches.values_of("glob") {
+        let globs: Vec<&str> = globs.collect();
+        thanks::scopes::set_globs(&connection, &sub_project, &globs);
+        thanks::scopes::apply(&log, &connection, &sub_project);
+    }
+
+    for glob in thanks::scopes::globs_of(&connection, &sub_project) {
+        println!("glob: {}", glob.glob);
+    }
+}
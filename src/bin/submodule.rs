@@ -0,0 +1,100 @@
+extern crate thanks;
+
+extern crate clap;
+extern crate diesel;
+
+extern crate git2;
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use diesel::prelude::*;
+use clap::{App, Arg};
+use slog::DrainExt;
+use git2::Repository;
+
+use std::fs;
+
+fn main() {
+    let matches = App::new("submodule")
+        .about("choose whether a submodule's commits are credited")
+        .arg(
+            Arg::with_name("filepath")
+                .short("p")
+                .long("path")
+                .help("filepath of the source code")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("project_name")
+                .short("n")
+                .long("name")
+                .help("name of the project")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("submodule")
+                .short("s")
+                .long("submodule")
+                .help("path of the submodule, like src/llvm")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("clone")
+                .short("c")
+                .long("clone")
+                .help("filepath of the submodule's clone, if it's not checked out")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("exclude")
+                .long("exclude")
+                .help("don't credit the submodule's commits"),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let project_name = matches.value_of("project_name").unwrap();
+    info!(log, "Project name: {}", project_name);
+    let path = matches.value_of("filepath").unwrap();
+    info!(log, "Path to {} repo: {}", project_name, path);
+    let submodule_path = matches.value_of("submodule").unwrap();
+    let include = !matches.is_present("exclude");
+    info!(log, "Submodule {} included: {}", submodule_path, include);
+
+    use thanks::schema::projects::dsl::{name, projects};
+    use thanks::models::Project;
+
+    let connection = thanks::establish_connection();
+
+    let project = projects
+        .filter(name.eq(project_name))
+        .first::<Project>(&connection)
+        .expect("Unknown project!");
+
+    if !project.follow_submodules {
+        warn!(log, "{} doesn't follow its submodules", project_name);
+    }
+
+    let repo = Repository::open(path).unwrap();
+    let repository = thanks::submodules::find_or_create(&connection, &repo, &project, submodule_path);
+
+    let clone = match matches.value_of("clone") {
+        Some(clone) => fs::canonicalize(clone)
+            .expect("Could not find the clone")
+            .to_str()
+            .unwrap()
+            .to_string(),
+        None => repository.repo_path.clone(),
+    };
+    info!(log, "Clone of {}: {}", submodule_path, clone);
+
+    thanks::repositories::configure(&connection, &repository, include, &clone);
+}
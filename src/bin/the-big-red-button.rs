@@ -97,7 +97,12 @@ fn delete_projects_db(log: &slog::Logger, connection: &PgConnection, project_nam
     // when diesel fixes this https://github.com/diesel-rs/diesel/issues/621
     let author_ids_to_delete = authors
         .left_outer_join(commits)
-        .filter(sql::<Bool>("commits.id IS NULL"))
+        .filter(sql::<Bool>(
+            "commits.sha IS NULL AND NOT EXISTS \
+             (SELECT 1 FROM co_authors WHERE co_authors.author_id = authors.id) \
+             AND NOT EXISTS (SELECT 1 FROM reviews WHERE reviews.author_id = authors.id) \
+             AND NOT EXISTS (SELECT 1 FROM handles WHERE handles.author_id = authors.id)",
+        ))
         .select(_author_id);
     diesel::delete(authors.filter(_author_id.eq(any(author_ids_to_delete))))
         .execute(connection)
@@ -121,6 +126,7 @@ fn delete_whole_db(log: &slog::Logger, connection: &PgConnection) {
     use thanks::schema::commits::dsl::*;
     use thanks::schema::projects::dsl::*;
     use thanks::schema::authors::dsl::*;
+    use thanks::schema::people::dsl::*;
 
     info!(log, "Deleting commits");
     diesel::delete(commits)
@@ -132,6 +138,11 @@ fn delete_whole_db(log: &slog::Logger, connection: &PgConnection) {
         .execute(connection)
         .expect("Error deleting authors");
 
+    info!(log, "Deleting people");
+    diesel::delete(people)
+        .execute(connection)
+        .expect("Error deleting people");
+
     info!(log, "Deleting releases");
     diesel::delete(releases)
         .execute(connection)
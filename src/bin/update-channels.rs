@@ -0,0 +1,66 @@
+extern crate thanks;
+
+extern crate clap;
+extern crate diesel;
+
+extern crate git2;
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use diesel::prelude::*;
+use clap::{App, Arg};
+use slog::DrainExt;
+use git2::Repository;
+
+fn main() {
+    let matches = App::new("update-channels")
+        .about("recompute the commits of a project's channels")
+        .arg(
+            Arg::with_name("filepath")
+                .short("p")
+                .long("path")
+                .help("filepath of the source code")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("project_name")
+                .short("n")
+                .long("name")
+                .help("name of the project")
+                .takes_value(true)
+                .required(true),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let project_name = matches.value_of("project_name").unwrap();
+    info!(log, "Project name: {}", project_name);
+    let path = matches.value_of("filepath").unwrap();
+    info!(log, "Path to {} repo: {}", project_name, path);
+
+    use thanks::schema::projects::dsl::{name, projects};
+    use thanks::models::Project;
+    use thanks::authors::AuthorStore;
+
+    let connection = thanks::establish_connection();
+
+    let project = projects
+        .filter(name.eq(project_name))
+        .first::<Project>(&connection)
+        .expect("Unknown project!");
+
+    let repo = Repository::open(path).unwrap();
+    let mut cache = AuthorStore::from_file(&connection, path);
+
+    for channel in thanks::channels::for_project(&connection, project.id) {
+        thanks::channels::sync(&log, &connection, &repo, &mut cache, &channel);
+    }
+
+    info!(log, "Done!");
+}
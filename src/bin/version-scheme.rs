@@ -0,0 +1,98 @@
+extern crate thanks;
+
+extern crate clap;
+extern crate diesel;
+
+extern crate git2;
+#[macro_use]
+extern crate slog;
+extern crate slog_term;
+
+use diesel::prelude::*;
+use clap::{App, Arg};
+use slog::DrainExt;
+use git2::Repository;
+
+use thanks::versions::VersionScheme;
+
+fn main() {
+    let matches = App::new("version-scheme")
+        .about("choose how a project's releases are ordered")
+        .arg(
+            Arg::with_name("project_name")
+                .short("n")
+                .long("name")
+                .help("name of the project")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("scheme")
+                .long("scheme")
+                .help("how the project's versions are ordered")
+                .takes_value(true)
+                .possible_values(VersionScheme::names()),
+        )
+        .arg(
+            Arg::with_name("filepath")
+                .short("p")
+                .long("path")
+                .help("filepath of the project's clone, to find when releases were tagged")
+                .takes_value(true),
+        )
+        .get_matches();
+
+    let log = slog::Logger::root(
+        slog_term::streamer().full().build().fuse(),
+        o!("version" => env!("CARGO_PKG_VERSION")),
+    );
+
+    let project_name = matches.value_of("project_name").unwrap();
+    info!(log, "Project name: {}", project_name);
+
+    use thanks::schema::projects::dsl::{name, projects};
+    use thanks::models::Project;
+
+    let connection = thanks::establish_connection();
+
+    let find_project = || {
+        projects
+            .filter(name.eq(project_name))
+            .first::<Project>(&connection)
+            .expect("Unknown project!")
+    };
+    let project = find_project();
+
+    let scheme = matches
+        .value_of("scheme")
+        .map(|scheme| VersionScheme::from_name(scheme).unwrap())
+        .unwrap_or_else(|| project.version_scheme());
+
+    // releases made before dates were recorded don't have one, and ordering by
+    // tag date needs all of them
+    let path = matches
+        .value_of("filepath")
+        .or_else(|| project.repo_path.as_ref().map(|p| &p[..]));
+    match path {
+        Some(path) => {
+            let repo = Repository::open(path).unwrap();
+            for release in thanks::releases::in_order(&connection, project.id) {
+                if !thanks::releases::record_date(&connection, &repo, &release) {
+                    warn!(log, "Couldn't find the tag of {}", release.version);
+                }
+            }
+        }
+        None if scheme == VersionScheme::TagDate => {
+            panic!("{} has no clone to find tag dates in; pass --path", project.name);
+        }
+        None => {}
+    }
+
+    if matches.is_present("scheme") {
+        thanks::projects::set_version_scheme(&connection, &project, scheme);
+        info!(log, "Ordering releases by {}", scheme.name());
+    }
+
+    let project = find_project();
+    println!("version scheme: {}", project.version_scheme().name());
+}
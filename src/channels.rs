@@ -0,0 +1,168 @@
+use models::{Channel, NewChannel, NewChannelCommit, Project, Release};
+
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use authors::AuthorStore;
+use releases;
+
+use git2::{Oid, Repository};
+
+use slog::Logger;
+
+use std::collections::HashSet;
+
+/// creates a channel along with the pseudo-release it's shown as
+///
+/// A channel tracks `branch`, and holds every commit on it that isn't
+/// reachable from `since`. Without `since` the channel starts at the latest
+/// release, which is what a beta channel wants; a nightly channel that tracks
+/// the project's branch would start at the beta branch instead.
+pub fn create(
+    conn: &PgConnection,
+    project_id: i32,
+    name: &str,
+    branch: &str,
+    since: Option<&str>,
+    link: &str,
+) -> Channel {
+    use schema::channels;
+
+    let release = releases::create_pseudo(conn, name, project_id, link);
+
+    let new_channel = NewChannel {
+        project_id: project_id,
+        release_id: release.id,
+        name: name,
+        branch: branch,
+        since: since,
+    };
+
+    diesel::insert_into(channels::table)
+        .values(&new_channel)
+        .get_result(conn)
+        .expect("Error saving new channel")
+}
+
+/// returns all channels of a project
+pub fn for_project(conn: &PgConnection, channel_project_id: i32) -> Vec<Channel> {
+    use schema::channels::dsl::*;
+
+    channels
+        .filter(project_id.eq(channel_project_id))
+        .order(id)
+        .load(conn)
+        .expect("Error loading channels")
+}
+
+/// the commits a channel holds, if the release is a channel's
+///
+/// Channel releases don't own any commits themselves, so their pages are made
+/// from these instead.
+pub fn commits_of(conn: &PgConnection, release: &Release) -> Option<Vec<String>> {
+    use schema::{channel_commits, channels};
+
+    let channel = match channels::table
+        .filter(channels::release_id.eq(release.id))
+        .first::<Channel>(conn)
+    {
+        Ok(channel) => channel,
+        Err(_) => {
+            return None;
+        }
+    };
+
+    Some(
+        channel_commits::table
+            .filter(channel_commits::channel_id.eq(channel.id))
+            .select(channel_commits::commit_sha)
+            .load(conn)
+            .expect("Error loading channel commits"),
+    )
+}
+
+/// recomputes the commits of a channel from the repository
+///
+/// A channel only records which commits it holds. They keep belonging to the
+/// project's branch, or to the release they shipped in, so the branch doesn't
+/// lose the contributors of a channel, and a channel that was promoted just
+/// starts over from the new release. Commits on the channel that aren't known
+/// yet are added to the project's branch.
+pub fn sync(
+    log: &Logger,
+    conn: &PgConnection,
+    repo: &Repository,
+    cache: &mut AuthorStore,
+    channel: &Channel,
+) {
+    use schema::{channel_commits, commits};
+    use diesel::expression::dsl::any;
+
+    let project = ::schema::projects::table
+        .find(channel.project_id)
+        .first::<Project>(conn)
+        .expect("could not find channel project");
+
+    let since = match channel.since {
+        Some(ref since) => since.clone(),
+        None => {
+            ::releases::latest(conn, channel.project_id)
+                .expect("channel has no release to start from")
+                .version
+        }
+    };
+
+    info!(
+        log,
+        "Syncing channel {} ({}..{})", channel.name, since, channel.branch
+    );
+
+    let ids = ::releases::get_new_commits(repo, &channel.branch, &since);
+    let shas: Vec<String> = ids.iter().map(|id| format!("{}", id)).collect();
+
+    let known: HashSet<String> = commits::table
+        .filter(commits::sha.eq(any(&shas)))
+        .select(commits::sha)
+        .load::<String>(conn)
+        .expect("Error loading channel commits")
+        .into_iter()
+        .collect();
+
+    let unknown: Vec<Oid> = ids.into_iter()
+        .filter(|id| !known.contains(&format!("{}", id)))
+        .collect();
+
+    if !unknown.is_empty() {
+        info!(
+            log,
+            "Adding {} new commits to {}",
+            unknown.len(),
+            project.branch
+        );
+        releases::assign_commits(log, repo, cache, &project.branch, unknown, project.id);
+    }
+
+    let new_channel_commits: Vec<_> = shas.iter()
+        .map(|sha| NewChannelCommit {
+            channel_id: channel.id,
+            commit_sha: sha,
+        })
+        .collect();
+
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::delete(channel_commits::table.filter(channel_commits::channel_id.eq(channel.id)))
+            .execute(conn)?;
+
+        // Postgres won't take too many parameters in one query
+        for chunk in new_channel_commits.chunks(10_000) {
+            diesel::insert_into(channel_commits::table)
+                .values(chunk)
+                .execute(conn)?;
+        }
+
+        Ok(())
+    }).expect("Error saving channel commits");
+
+    info!(log, "Channel {} holds {} commits", channel.name, shas.len());
+}
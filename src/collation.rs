@@ -0,0 +1,565 @@
+use std::collections::HashMap;
+use std::env;
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::canonical_combining_class;
+
+// The Default Unicode Collation Element Table, from
+// http://www.unicode.org/Public/UCA/13.0.0/allkeys.txt
+const ALLKEYS: &'static str = include_str!("../data/allkeys.txt");
+
+// DUCET weights are shifted left by this much, which leaves room to put
+// tailored characters between two neighbouring weights
+const TAILORING_ROOM: u32 = 8;
+
+const MIN_SECONDARY: u32 = 0x0020 << TAILORING_ROOM;
+const MIN_TERTIARY: u32 = 0x0002 << TAILORING_ROOM;
+
+lazy_static! {
+    static ref DUCET: Table = Table::parse(ALLKEYS);
+
+    static ref DEFAULT: Collator = match env::var("COLLATION_LOCALE") {
+        Ok(locale) => Collator::for_locale(&locale)
+            .expect(&format!("Unknown collation locale {}", locale)),
+        Err(_) => Collator::new(),
+    };
+}
+
+/// sorts strings with the Unicode Collation Algorithm
+///
+/// This uses the root collation unless `COLLATION_LOCALE` is set, in which
+/// case the tailoring for that locale is used.
+pub fn sort(strings: &mut Vec<String>) {
+    DEFAULT.sort(strings);
+}
+
+/// the sort key of a string under the default collator
+pub fn sort_key(string: &str) -> SortKey {
+    DEFAULT.sort_key(string)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Element {
+    primary: u32,
+    secondary: u32,
+    tertiary: u32,
+    variable: bool,
+}
+
+impl Element {
+    fn new(primary: u32, secondary: u32, tertiary: u32) -> Element {
+        Element {
+            primary: primary << TAILORING_ROOM,
+            secondary: secondary << TAILORING_ROOM,
+            tertiary: tertiary << TAILORING_ROOM,
+            variable: false,
+        }
+    }
+}
+
+struct Table {
+    elements: HashMap<Vec<char>, Vec<Element>>,
+    // (first, last, base) for scripts with their own implicit weights
+    implicit: Vec<(u32, u32, u32)>,
+    // the length of the longest contraction
+    max_len: usize,
+}
+
+impl Table {
+    fn empty() -> Table {
+        Table {
+            elements: HashMap::new(),
+            implicit: Vec::new(),
+            max_len: 0,
+        }
+    }
+
+    fn parse(data: &str) -> Table {
+        let mut table = Table::empty();
+
+        for line in data.lines() {
+            let line = match line.find('#') {
+                Some(i) => &line[..i],
+                None => line,
+            }.trim();
+
+            if line.is_empty() || line.starts_with("@version") {
+                continue;
+            }
+
+            if line.starts_with("@implicitweights") {
+                // @implicitweights 17000..18AFF; FB00
+                let mut parts = line["@implicitweights".len()..].split(';');
+                let range = parts.next().unwrap().trim();
+                let base = parts.next().unwrap().trim();
+                let mut bounds = range.split("..");
+                let first = u32::from_str_radix(bounds.next().unwrap(), 16).unwrap();
+                let last = u32::from_str_radix(bounds.next().unwrap(), 16).unwrap();
+                let base = u32::from_str_radix(base, 16).unwrap();
+                table.implicit.push((first, last, base));
+                continue;
+            }
+
+            // 0041 ; [.1FA2.0020.0008] # LATIN CAPITAL LETTER A
+            let mut parts = line.split(';');
+            let key: Vec<char> = parts
+                .next()
+                .unwrap()
+                .split_whitespace()
+                .map(|cp| ::std::char::from_u32(u32::from_str_radix(cp, 16).unwrap()).unwrap())
+                .collect();
+
+            let elements = parts
+                .next()
+                .unwrap()
+                .trim()
+                .split(']')
+                .filter(|e| !e.is_empty())
+                .map(|e| {
+                    // [.1FA2.0020.0008 or [*0209.0020.0002
+                    let variable = &e[1..2] == "*";
+                    let weights: Vec<u32> = e[2..]
+                        .split('.')
+                        .map(|w| u32::from_str_radix(w, 16).unwrap())
+                        .collect();
+                    let mut element = Element::new(weights[0], weights[1], weights[2]);
+                    element.variable = variable;
+                    element
+                })
+                .collect();
+
+            table.insert(key, elements);
+        }
+
+        table
+    }
+
+    fn insert(&mut self, key: Vec<char>, elements: Vec<Element>) {
+        if key.len() > self.max_len {
+            self.max_len = key.len();
+        }
+        self.elements.insert(key, elements);
+    }
+}
+
+/// how punctuation, spaces and symbols are weighted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variable {
+    /// they are compared like letters
+    NonIgnorable,
+    /// they are only compared when everything else is equal, so that
+    /// "Anne-Marie" and "Anne Marie" sort next to each other
+    Shifted,
+}
+
+/// a sort key, which can be compared directly instead of the strings
+///
+/// Strings with identical collation weights are ordered by their code points,
+/// so that the order is deterministic.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SortKey {
+    weights: Vec<u32>,
+    nfd: Vec<char>,
+    string: String,
+}
+
+/// an implementation of the Unicode Collation Algorithm (UTS #10)
+///
+/// Collation uses the DUCET, optionally tailored for a locale with rules in
+/// a small subset of the LDML syntax.
+pub struct Collator {
+    tailoring: Table,
+    variable: Variable,
+}
+
+impl Collator {
+    /// a collator for the untailored root collation, with shifted variables
+    pub fn new() -> Collator {
+        Collator {
+            tailoring: Table::empty(),
+            variable: Variable::Shifted,
+        }
+    }
+
+    /// a collator tailored for a locale, like `sv` or `es`
+    ///
+    /// Returns `None` for locales without a tailoring.
+    pub fn for_locale(locale: &str) -> Option<Collator> {
+        let language = locale.split(|c| c == '_' || c == '-').next().unwrap();
+
+        let rules = match language {
+            "root" | "en" | "de" | "fr" | "it" | "nl" | "pt" => "",
+            "sv" | "fi" => "&z < å <<< Å < ä <<< Ä < ö <<< Ö",
+            "da" | "nb" | "nn" | "no" => "&z < æ <<< Æ < ø <<< Ø < å <<< Å",
+            "es" => "&n < ñ <<< Ñ",
+            _ => return None,
+        };
+
+        Some(Collator::with_rules(rules).unwrap())
+    }
+
+    /// a collator tailored with rules like `&z < å <<< Å < ä`
+    ///
+    /// `&` resets to an anchor, and `<`, `<<` and `<<<` put the next string
+    /// right after the previous one with a primary, secondary or tertiary
+    /// difference.
+    pub fn with_rules(rules: &str) -> Result<Collator, String> {
+        let mut collator = Collator::new();
+        let mut current: Option<Vec<Element>> = None;
+        let mut level = None;
+
+        for token in rules.split_whitespace() {
+            match token {
+                "<" => level = Some(1),
+                "<<" => level = Some(2),
+                "<<<" => level = Some(3),
+                _ if token.starts_with('&') => {
+                    let elements = collator.elements(&token[1..]);
+                    if elements.is_empty() {
+                        return Err(format!("Cannot reset to ignorable {}", &token[1..]));
+                    }
+                    current = Some(elements);
+                    level = None;
+                }
+                _ => {
+                    let (mut elements, level) = match (current.take(), level.take()) {
+                        (Some(elements), Some(level)) => (elements, level),
+                        _ => return Err(format!("Expected a relation before {}", token)),
+                    };
+
+                    {
+                        let last = elements.last_mut().unwrap();
+                        match level {
+                            1 => {
+                                last.primary += 1;
+                                last.secondary = MIN_SECONDARY;
+                                last.tertiary = MIN_TERTIARY;
+                                last.variable = false;
+                            }
+                            2 => {
+                                last.secondary += 1;
+                                last.tertiary = MIN_TERTIARY;
+                            }
+                            _ => last.tertiary += 1,
+                        }
+                    }
+
+                    collator.tailoring.insert(token.nfd().collect(), elements.clone());
+                    current = Some(elements);
+                }
+            }
+        }
+
+        Ok(collator)
+    }
+
+    pub fn variable(mut self, variable: Variable) -> Collator {
+        self.variable = variable;
+        self
+    }
+
+    /// sorts strings in place
+    pub fn sort(&self, strings: &mut Vec<String>) {
+        let mut keyed: Vec<_> = strings.drain(..).map(|s| self.sort_key(&s)).collect();
+        keyed.sort();
+        strings.extend(keyed.into_iter().map(|key| key.string));
+    }
+
+    pub fn sort_key(&self, string: &str) -> SortKey {
+        let nfd: Vec<char> = string.nfd().collect();
+        let elements = self.elements_nfd(nfd.clone());
+
+        let (levels, count) = match self.variable {
+            Variable::NonIgnorable => {
+                let levels = elements
+                    .into_iter()
+                    .map(|e| [e.primary, e.secondary, e.tertiary, 0])
+                    .collect();
+                (levels, 3)
+            }
+            Variable::Shifted => (shift(elements), 4),
+        };
+
+        // the non-zero weights of each level in turn, separated by zeros
+        let mut weights = Vec::new();
+        for level in 0..count {
+            if level > 0 {
+                weights.push(0);
+            }
+            weights.extend(levels.iter().map(|l| l[level]).filter(|&w| w != 0));
+        }
+
+        SortKey {
+            weights: weights,
+            nfd: nfd,
+            string: string.to_string(),
+        }
+    }
+
+    fn elements(&self, string: &str) -> Vec<Element> {
+        self.elements_nfd(string.nfd().collect())
+    }
+
+    fn lookup(&self, key: &[char]) -> Option<&Vec<Element>> {
+        self.tailoring
+            .elements
+            .get(key)
+            .or_else(|| DUCET.elements.get(key))
+    }
+
+    fn elements_nfd(&self, mut chars: Vec<char>) -> Vec<Element> {
+        let max_len = ::std::cmp::max(self.tailoring.max_len, DUCET.max_len);
+        let mut result = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            // find the longest contraction starting here
+            let longest = (1..max_len + 1)
+                .rev()
+                .filter(|&len| i + len <= chars.len())
+                .find(|&len| self.lookup(&chars[i..i + len]).is_some());
+
+            let len = match longest {
+                Some(len) => len,
+                None => {
+                    result.extend(implicit(chars[i]));
+                    i += 1;
+                    continue;
+                }
+            };
+
+            // extend it with unblocked non-starters that follow it, which is
+            // how a contraction still matches when other combining marks got
+            // in the way
+            let mut matched: Vec<char> = chars[i..i + len].to_vec();
+            let mut j = i + len;
+            let mut skipped_ccc = 0;
+
+            while j < chars.len() {
+                let ccc = canonical_combining_class(chars[j]);
+                if ccc == 0 {
+                    break;
+                }
+
+                if skipped_ccc < ccc {
+                    matched.push(chars[j]);
+                    if self.lookup(&matched).is_some() {
+                        chars.remove(j);
+                        continue;
+                    }
+                    matched.pop();
+                }
+
+                skipped_ccc = ::std::cmp::max(skipped_ccc, ccc);
+                j += 1;
+            }
+
+            result.extend(self.lookup(&matched).unwrap().iter().cloned());
+            i += len;
+        }
+
+        result
+    }
+}
+
+/// the weights of characters that aren't in the table
+fn implicit(c: char) -> Vec<Element> {
+    let cp = c as u32;
+
+    let (base, offset) = match DUCET
+        .implicit
+        .iter()
+        .find(|&&(first, last, _)| first <= cp && cp <= last)
+    {
+        Some(&(_, _, base)) => {
+            // scripts with a supplement block count from the start of the
+            // first block, so the supplement sorts after it
+            let first = DUCET
+                .implicit
+                .iter()
+                .filter(|&&(_, _, b)| b == base)
+                .map(|&(first, _, _)| first)
+                .min()
+                .unwrap();
+            (base, cp - first)
+        }
+        None => {
+            let base = if is_core_han(cp) {
+                0xFB40
+            } else if is_other_han(cp) {
+                0xFB80
+            } else {
+                0xFBC0
+            };
+            (base + (cp >> 15), cp & 0x7FFF)
+        }
+    };
+
+    vec![
+        Element::new(base, 0x0020, 0x0002),
+        Element::new(offset | 0x8000, 0, 0),
+    ]
+}
+
+// Unified_Ideograph characters in the CJK Unified Ideographs and CJK
+// Compatibility Ideographs blocks, as of Unicode 13.0
+fn is_core_han(cp: u32) -> bool {
+    match cp {
+        0x4E00..=0x9FFC => true,
+        0xFA0E | 0xFA0F | 0xFA11 | 0xFA13 | 0xFA14 | 0xFA1F | 0xFA21 | 0xFA23 | 0xFA24 => true,
+        0xFA27..=0xFA29 => true,
+        _ => false,
+    }
+}
+
+// the other Unified_Ideograph characters, as of Unicode 13.0
+fn is_other_han(cp: u32) -> bool {
+    match cp {
+        0x3400..=0x4DBF => true,
+        0x20000..=0x2A6DD => true,
+        0x2A700..=0x2B734 => true,
+        0x2B740..=0x2B81D => true,
+        0x2B820..=0x2CEA1 => true,
+        0x2CEB0..=0x2EBE0 => true,
+        0x30000..=0x3134A => true,
+        _ => false,
+    }
+}
+
+/// applies the shifted variable weighting, which moves the primary weight of
+/// variable elements to a fourth level
+fn shift(elements: Vec<Element>) -> Vec<[u32; 4]> {
+    let mut after_variable = false;
+
+    elements
+        .into_iter()
+        .map(|e| {
+            if e.primary == 0 && e.secondary == 0 && e.tertiary == 0 {
+                [0, 0, 0, 0]
+            } else if e.variable {
+                after_variable = true;
+                [0, 0, 0, e.primary]
+            } else if e.primary == 0 && after_variable {
+                [0, 0, 0, 0]
+            } else {
+                if e.primary != 0 {
+                    after_variable = false;
+                }
+                [e.primary, e.secondary, e.tertiary, 0xFFFF_FFFF]
+            }
+        })
+        .collect()
+}
+
+/// checks that the strings in a CollationTest file, one per line as hex code
+/// points, are in sorted order
+///
+/// Strings with the same weights are ordered by `tie_break`.
+#[cfg(test)]
+fn check_order<F>(collator: &Collator, data: &str, tie_break: F)
+where
+    F: Fn(&str, &SortKey) -> String,
+{
+    let mut previous: Option<(String, SortKey, String)> = None;
+    let mut failures = Vec::new();
+
+    for line in data.lines().filter(|l| !l.starts_with('#') && !l.trim().is_empty()) {
+        let code_points = line.split(';').next().unwrap().trim();
+        let string: String = code_points
+            .split(' ')
+            .map(|cp| ::std::char::from_u32(u32::from_str_radix(cp, 16).unwrap()).unwrap())
+            .collect();
+        let key = collator.sort_key(&string);
+        let tie = tie_break(&string, &key);
+
+        if let Some((ref previous_line, ref previous_key, ref previous_tie)) = previous {
+            if (&previous_key.weights, previous_tie) > (&key.weights, &tie) {
+                failures.push(format!("{} > {}", previous_line, code_points));
+            }
+        }
+        previous = Some((code_points.to_string(), key, tie));
+    }
+
+    assert!(failures.is_empty(), "{} misordered lines, starting with {:?}", failures.len(),
+        &failures[..::std::cmp::min(failures.len(), 10)]);
+}
+
+#[test]
+fn test_unicode_collate_order() {
+    // recorded orderings from Perl's Unicode::Collate rather than Unicode's
+    // own test data, as an extra check of the names and tailorings this crate
+    // cares about; canonically equivalent strings can come in either order
+    let nfd = |_: &str, key: &SortKey| key.nfd.iter().collect();
+
+    check_order(
+        &Collator::new().variable(Variable::NonIgnorable),
+        include_str!("../data/collation-test-non-ignorable.txt"),
+        &nfd,
+    );
+    check_order(
+        &Collator::new().variable(Variable::Shifted),
+        include_str!("../data/collation-test-shifted.txt"),
+        &nfd,
+    );
+}
+
+#[test]
+#[ignore]
+fn test_official_collation_conformance() {
+    // the official test data, where strings with the same weights are in code
+    // point order; ignored until data/fetch-collation-test.sh has vendored it
+    use std::fs::File;
+    use std::io::Read;
+
+    fn read(file: &str) -> String {
+        let path = format!("{}/data/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let mut data = String::new();
+        File::open(&path)
+            .and_then(|mut f| f.read_to_string(&mut data))
+            .expect(&format!("Run data/fetch-collation-test.sh to vendor {}", file));
+        let version = data.lines()
+            .take_while(|line| line.starts_with('#'))
+            .any(|line| line.contains("13.0.0"));
+        assert!(version, "{} isn't for UCA 13.0.0, like data/allkeys.txt", file);
+        data
+    }
+    let code_points = |string: &str, _: &SortKey| string.to_string();
+
+    check_order(
+        &Collator::new().variable(Variable::NonIgnorable),
+        &read("CollationTest_NON_IGNORABLE_SHORT.txt"),
+        &code_points,
+    );
+    check_order(
+        &Collator::new().variable(Variable::Shifted),
+        &read("CollationTest_SHIFTED_SHORT.txt"),
+        &code_points,
+    );
+}
+
+#[test]
+fn test_collation_tailoring() {
+    fn sorted(collator: &Collator, strings: &[&str]) -> Vec<String> {
+        let mut strings = strings.iter().map(|s| s.to_string()).collect();
+        collator.sort(&mut strings);
+        strings
+    }
+
+    let names = ["Östen", "Zlatan", "Åsa", "Anna", "Ärla", "åsa", "Olof"];
+
+    assert_eq!(
+        sorted(&Collator::new(), &names),
+        vec!["Anna", "Ärla", "åsa", "Åsa", "Olof", "Östen", "Zlatan"]
+    );
+    assert_eq!(
+        sorted(&Collator::for_locale("sv_SE").unwrap(), &names),
+        vec!["Anna", "Olof", "Zlatan", "åsa", "Åsa", "Ärla", "Östen"]
+    );
+    assert_eq!(
+        sorted(&Collator::for_locale("es").unwrap(), &["nube", "ñu", "Ñandú", "oca"]),
+        vec!["nube", "Ñandú", "ñu", "oca"]
+    );
+
+    assert!(Collator::for_locale("xx").is_none());
+    assert!(Collator::with_rules("å < ä").is_err());
+}
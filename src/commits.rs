@@ -1,22 +1,305 @@
-use models::{Commit, NewCommit};
+use models::NewCoAuthor;
 use models::Author;
-use models::Release;
+use models::{Project, Release};
 
 use diesel;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 
-pub fn create<'a>(conn: &PgConnection, sha: &'a str, author: &Author, release: &Release) -> Commit {
-    use schema::commits;
+use git2;
+use git2::{DiffFormat, ObjectType, Oid, Repository};
 
-    let new_commit = NewCommit {
-        sha: sha,
-        release_id: release.id,
-        author_id: author.id,
+/// computes an identifier for the change a commit introduces
+///
+/// This is similar in spirit to `git patch-id`: only the paths touched and the
+/// added and removed lines are hashed, with all ASCII whitespace stripped, so
+/// a commit that was cherry-picked onto another branch gets the same id as the
+/// original even though its sha, parents and line numbers differ. Bytes of
+/// other characters are kept, as they may be part of a multi-byte one.
+///
+/// Merge commits don't have a single patch, so they get `None`, and so do
+/// commits that don't change any lines, like ones that only touch binary
+/// files, modes or names: unrelated commits would share their id. Objects that
+/// can't be read get `None` too, rather than stopping the import.
+pub fn patch_id(repo: &Repository, commit: &git2::Commit) -> Option<String> {
+    if commit.parent_count() > 1 {
+        return None;
+    }
+
+    let tree = commit.tree().ok()?;
+    let parent_tree = match commit.parent_count() {
+        0 => None,
+        _ => Some(commit.parent(0).ok()?.tree().ok()?),
+    };
+
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+        .ok()?;
+
+    let mut patch = Vec::new();
+    let mut changes_lines = false;
+    diff.print(DiffFormat::Patch, |delta, _, line| {
+        match line.origin() {
+            'F' => {
+                // the file header contains blob ids, which are different
+                // for a backport, so only use the paths
+                for file in &[delta.old_file(), delta.new_file()] {
+                    if let Some(path) = file.path_bytes() {
+                        patch.extend_from_slice(path);
+                    }
+                    patch.push(b'\n');
+                }
+            }
+            origin @ '+' | origin @ '-' => {
+                changes_lines = true;
+                patch.push(origin as u8);
+                patch.extend(
+                    line.content()
+                        .iter()
+                        .filter(|b| !b.is_ascii_whitespace()),
+                );
+                patch.push(b'\n');
+            }
+            _ => {}
+        }
+        true
+    }).ok()?;
+
+    if !changes_lines {
+        return None;
+    }
+
+    Oid::hash_object(ObjectType::Blob, &patch)
+        .ok()
+        .map(|id| format!("{}", id))
+}
+
+/// the trailers of a commit message, as `(key, value)` pairs
+///
+/// Like `git interpret-trailers`, only the last paragraph is looked at, and
+/// only if all of its lines are trailers, so a paragraph of prose that happens
+/// to have a colon in it isn't mistaken for one.
+pub fn trailers(message: &str) -> Vec<(String, String)> {
+    let paragraph = message
+        .trim_right()
+        .rsplit("\n\n")
+        .next()
+        .unwrap_or("");
+
+    let trailers: Vec<_> = paragraph
+        .lines()
+        .filter_map(|line| {
+            let colon = line.find(':')?;
+            let (key, value) = (&line[..colon], line[colon + 1..].trim());
+            let is_token = !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '-');
+
+            if is_token && !value.is_empty() {
+                Some((key.to_string(), value.to_string()))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    if trailers.len() == paragraph.lines().count() {
+        trailers
+    } else {
+        Vec::new()
+    }
+}
+
+/// the people a commit credits with `Co-authored-by:` trailers
+pub fn co_authors(message: &str) -> Vec<(String, String)> {
+    trailers(message)
+        .into_iter()
+        .filter(|&(ref key, _)| key.eq_ignore_ascii_case("co-authored-by"))
+        .filter_map(|(_, value)| parse_identity(&value))
+        .collect()
+}
+
+/// splits `Name <email>` into its name and email
+pub fn parse_identity(identity: &str) -> Option<(String, String)> {
+    let open = identity.find('<')?;
+    let close = identity.rfind('>')?;
+    if close < open {
+        return None;
+    }
+
+    let name = identity[..open].trim();
+    let email = identity[open + 1..close].trim();
+    if name.is_empty() || email.is_empty() {
+        return None;
+    }
+
+    Some((name.to_string(), email.to_string()))
+}
+
+/// records the co-authors of a commit, besides its author
+pub fn add_co_authors(conn: &PgConnection, sha: &str, authors: &[Author]) {
+    use schema::co_authors;
+
+    let new_co_authors: Vec<_> = authors
+        .iter()
+        .map(|author| NewCoAuthor {
+            commit_sha: sha,
+            author_id: author.id,
+        })
+        .collect();
+
+    diesel::insert_into(co_authors::table)
+        .values(&new_co_authors)
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .expect("Error saving co-authors");
+}
+
+/// the number of credited commits each co-author has
+pub fn co_author_counts(conn: &PgConnection) -> Vec<(i32, i64)> {
+    use schema::{co_authors, commits};
+    use diesel::expression::dsl::sql;
+    use diesel::types::BigInt;
+
+    co_authors::table
+        .inner_join(commits::table)
+        .filter(commits::backport_of.is_null())
+        .filter(commits::excluded.eq(false))
+        .select((co_authors::author_id, sql::<BigInt>("COUNT(*)")))
+        .group_by(co_authors::author_id)
+        .load(conn)
+        .expect("Error counting co-authors")
+}
+
+/// the co-authors of a release, of the project's part of it for sub-projects
+pub fn co_author_ids(conn: &PgConnection, release: &Release, project: &Project) -> Vec<i32> {
+    use schema::{co_authors, commit_scopes, commits};
+    use diesel::expression::dsl::any;
+
+    let mut query = co_authors::table
+        .inner_join(commits::table)
+        .filter(commits::excluded.eq(false))
+        .select(co_authors::author_id)
+        .distinct()
+        .into_boxed();
+
+    query = match ::channels::commits_of(conn, release) {
+        Some(shas) => query.filter(commits::sha.eq(any(shas))),
+        None => query.filter(commits::release_id.eq(release.id)),
     };
 
-    diesel::insert_into(commits::table)
-        .values(&new_commit)
-        .get_result(conn)
-        .expect("Error saving new commit")
+    if project.parent_id.is_some() {
+        let scoped = commit_scopes::table
+            .filter(commit_scopes::project_id.eq(project.id))
+            .select(commit_scopes::commit_sha);
+        query = query.filter(commits::sha.eq(any(scoped)));
+    }
+
+    query.load(conn).expect("Error loading co-authors")
+}
+
+#[test]
+fn test_co_authors() {
+    let message = "Implement the thing\n\
+                   \n\
+                   It works now: really.\n\
+                   \n\
+                   Co-authored-by: Ana Lopez <ana@example.com>\n\
+                   co-authored-by:Jo Smith <jo@example.org>\n\
+                   Signed-off-by: Someone Else <else@example.net>\n";
+
+    assert_eq!(
+        co_authors(message),
+        vec![
+            ("Ana Lopez".to_string(), "ana@example.com".to_string()),
+            ("Jo Smith".to_string(), "jo@example.org".to_string()),
+        ]
+    );
+
+    // prose in the last paragraph means there are no trailers
+    assert!(co_authors("Fix it\n\nThanks to: Co-authored-by: A <a@example.com>\nand others").is_empty());
+    assert!(co_authors("Co-authored-by: A <a@example.com>\n\nMore text").is_empty());
+    assert!(co_authors("Fix\n\nCo-authored-by: nobody").is_empty());
+
+    assert_eq!(parse_identity("<a@example.com>"), None);
+}
+
+/// a repository in a temporary directory, for tests to commit to, which is
+/// removed again even when the test fails
+#[cfg(test)]
+pub struct TempRepo {
+    path: ::std::path::PathBuf,
+    repo: Repository,
+}
+
+#[cfg(test)]
+impl TempRepo {
+    pub fn new(name: &str) -> TempRepo {
+        let dir = format!("thanks-{}-{}", name, ::std::process::id());
+        let path = ::std::env::temp_dir().join(dir);
+        let repo = Repository::init(&path).unwrap();
+
+        TempRepo {
+            path: path,
+            repo: repo,
+        }
+    }
+}
+
+#[cfg(test)]
+impl ::std::ops::Deref for TempRepo {
+    type Target = Repository;
+
+    fn deref(&self) -> &Repository {
+        &self.repo
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempRepo {
+    fn drop(&mut self) {
+        let _ = ::std::fs::remove_dir_all(&self.path);
+    }
+}
+
+#[test]
+fn test_patch_id() {
+    use git2::Signature;
+
+    let repo = TempRepo::new("patch-id");
+    let sig = Signature::now("Ana Lopez", "ana@example.com").unwrap();
+
+    let commit_file = |content: &str, parents: &[&git2::Commit]| {
+        let mut root = repo.treebuilder(None).unwrap();
+        let blob = repo.blob(content.as_bytes()).unwrap();
+        root.insert("lib.rs", blob, 0o100644).unwrap();
+        let tree = repo.find_tree(root.write().unwrap()).unwrap();
+        let id = repo.commit(None, &sig, &sig, "commit", &tree, parents).unwrap();
+        repo.find_commit(id).unwrap()
+    };
+    let id_of = |commit: &git2::Commit| patch_id(&repo, commit).unwrap();
+
+    let base = commit_file("fn main() {\n}\n", &[]);
+    let change = commit_file("fn main() {\n    run(1);\n}\n", &[&base]);
+
+    // changes that only differ in whitespace are the same change
+    let spaced = commit_file("fn main() {\n\trun( 1 );\n}\n", &[&base]);
+    assert_eq!(id_of(&change), id_of(&spaced));
+
+    // non-ASCII bytes are kept whole, even the ones that look like whitespace
+    // on their own, like the 0xA0 in "à" and the 0x85 in "Å"
+    let grave = commit_file("fn main() {\n    // là\n}\n", &[&base]);
+    let ring = commit_file("fn main() {\n    // lÅ\n}\n", &[&base]);
+    assert!(id_of(&grave) != id_of(&ring));
+
+    // a backport onto a branch where the file has moved on is still the same
+    // change, though its sha and line numbers aren't
+    let branch = commit_file("// 1.15\n\nfn main() {\n}\n", &[&base]);
+    let backport = commit_file("// 1.15\n\nfn main() {\n    run(1);\n}\n", &[&branch]);
+    assert!(backport.id() != change.id());
+    assert_eq!(id_of(&backport), id_of(&change));
+    assert!(id_of(&backport) != id_of(&grave));
+
+    // a binary change has no lines, so any other change to the file would get
+    // the same id
+    let binary = commit_file("\0version 1", &[&base]);
+    let rebuilt = commit_file("\0version 2", &[&binary]);
+    assert_eq!(patch_id(&repo, &rebuilt), None);
 }
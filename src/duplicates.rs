@@ -0,0 +1,294 @@
+use models::Author;
+
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+
+/// names at least this long may differ by a typo or two and still be the same
+const MIN_FUZZY_LENGTH: usize = 8;
+const MAX_DISTANCE: usize = 2;
+
+/// why two authors look like the same contributor
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum Reason {
+    /// they commit with the same email under different names
+    SameEmail,
+    /// they're linked to the same GitHub login, like a `users.noreply` email
+    /// and the login set on another identity
+    SameLogin,
+    /// their names are the same apart from case, accents and spacing
+    SameName,
+    /// their names are this many edits apart
+    SimilarName(usize),
+}
+
+impl Reason {
+    /// how sure we are, in percent
+    pub fn confidence(&self) -> u32 {
+        match *self {
+            Reason::SameEmail => 90,
+            Reason::SameLogin => 85,
+            Reason::SameName => 70,
+            Reason::SimilarName(distance) => 60 - 10 * distance as u32,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match *self {
+            Reason::SameEmail => "same email".to_string(),
+            Reason::SameLogin => "same GitHub login".to_string(),
+            Reason::SameName => "same name".to_string(),
+            Reason::SimilarName(1) => "names one edit apart".to_string(),
+            Reason::SimilarName(distance) => format!("names {} edits apart", distance),
+        }
+    }
+}
+
+/// two authors that are likely the same contributor, and the newer one should
+/// map to the older one
+#[derive(Debug)]
+pub struct Suggestion {
+    pub from: Author,
+    pub into: Author,
+    /// strongest first
+    pub reasons: Vec<Reason>,
+}
+
+impl Suggestion {
+    pub fn confidence(&self) -> u32 {
+        self.reasons[0].confidence()
+    }
+
+    /// the line for the repository's `.mailmap`
+    pub fn mailmap_line(&self) -> String {
+        ::authors::mailmap_line(&self.from, &self.into)
+    }
+}
+
+/// looks through every author for likely duplicates, most likely first
+pub fn report(conn: &PgConnection) -> Vec<Suggestion> {
+    use schema::authors::dsl::*;
+
+    let all: Vec<Author> = authors.load(conn).expect("Error loading authors");
+    suggest(all)
+}
+
+/// pairs up authors that look like the same contributor, most likely first
+///
+/// Authors that already are identities of the same person aren't suggested
+/// again.
+pub fn suggest(all: Vec<Author>) -> Vec<Suggestion> {
+    let mut reasons: HashMap<(usize, usize), HashSet<Reason>> = HashMap::new();
+    {
+        let mut add = |a: usize, b: usize, reason: Reason| {
+            let pair = (cmp::min(a, b), cmp::max(a, b));
+            reasons.entry(pair).or_insert_with(HashSet::new).insert(reason);
+        };
+
+        let mut by_email: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut by_login: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut by_name: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut by_word: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut names = Vec::with_capacity(all.len());
+
+        for (i, author) in all.iter().enumerate() {
+            by_email
+                .entry(author.email.to_lowercase())
+                .or_insert_with(Vec::new)
+                .push(i);
+
+            let login = author
+                .github_login
+                .as_ref()
+                .map(|login| &login[..])
+                .or_else(|| ::authors::github_login(&author.email));
+            if let Some(login) = login {
+                by_login
+                    .entry(login.to_lowercase())
+                    .or_insert_with(Vec::new)
+                    .push(i);
+            }
+
+            // names with nothing comparable left, like ones made only of
+            // punctuation, don't say anything about who the author is
+            let name = comparable(&author.name);
+            if !name.is_empty() {
+                by_name.entry(name.clone()).or_insert_with(Vec::new).push(i);
+                for word in name.split(' ') {
+                    by_word.entry(word.to_string()).or_insert_with(Vec::new).push(i);
+                }
+            }
+            names.push(name);
+        }
+
+        for group in by_email.values() {
+            each_pair(group, |a, b| add(a, b, Reason::SameEmail));
+        }
+        for group in by_login.values() {
+            each_pair(group, |a, b| add(a, b, Reason::SameLogin));
+        }
+        for group in by_name.values() {
+            each_pair(group, |a, b| add(a, b, Reason::SameName));
+        }
+
+        // only names that share a word are compared, as comparing every pair
+        // would take too long
+        let mut compared = HashSet::new();
+        for group in by_word.values() {
+            each_pair(group, |a, b| {
+                let (first, second) = (&names[a], &names[b]);
+                if first == second || !compared.insert((a, b)) {
+                    return;
+                }
+                if cmp::min(first.chars().count(), second.chars().count()) < MIN_FUZZY_LENGTH {
+                    return;
+                }
+                let distance = distance(first, second);
+                if distance <= MAX_DISTANCE {
+                    add(a, b, Reason::SimilarName(distance));
+                }
+            });
+        }
+    }
+
+    let mut suggestions: Vec<Suggestion> = reasons
+        .into_iter()
+        .filter(|&((a, b), _)| !same_person(&all[a], &all[b]))
+        .map(|((a, b), reasons)| {
+            let (older, newer) = if all[a].id < all[b].id { (a, b) } else { (b, a) };
+            let mut reasons: Vec<Reason> = reasons.into_iter().collect();
+            // the most telling reason first
+            reasons.sort_by(|a, b| (b.confidence(), a).cmp(&(a.confidence(), b)));
+            Suggestion {
+                from: all[newer].clone(),
+                into: all[older].clone(),
+                reasons: reasons,
+            }
+        })
+        .collect();
+
+    // the likeliest duplicates first, then the ones with the most reasons
+    suggestions.sort_by(|a, b| {
+        (b.confidence(), b.reasons.len(), a.into.id, a.from.id).cmp(&(
+            a.confidence(),
+            a.reasons.len(),
+            b.into.id,
+            b.from.id,
+        ))
+    });
+    suggestions
+}
+
+fn each_pair<F: FnMut(usize, usize)>(group: &[usize], mut f: F) {
+    for (i, &a) in group.iter().enumerate() {
+        for &b in &group[i + 1..] {
+            f(a, b);
+        }
+    }
+}
+
+fn same_person(a: &Author, b: &Author) -> bool {
+    a.person_id.is_some() && a.person_id == b.person_id
+}
+
+/// the name in lower case, without accents, and with its words separated by
+/// single spaces
+fn comparable(name: &str) -> String {
+    let name: String = name.nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(|c| c.to_lowercase())
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// the Levenshtein distance between two strings, in characters
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..b.len() + 1).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == *cb { 0 } else { 1 };
+            let insertion = current[j] + 1;
+            let deletion = previous[j + 1] + 1;
+            current.push(cmp::min(substitution, cmp::min(insertion, deletion)));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[test]
+fn test_duplicates() {
+    let author = |id: i32, name: &str, email: &str| Author {
+        id: id,
+        name: name.to_string(),
+        email: email.to_string(),
+        visible: true,
+        person_id: None,
+        github_login: None,
+        anonymous: false,
+    };
+
+    assert_eq!(comparable("  José  O'Neil "), "jose o neil");
+    assert_eq!(comparable("???"), "");
+    assert_eq!(distance("alexander", "alexandre"), 2);
+    assert_eq!(distance("", "abc"), 3);
+
+    let suggestions = suggest(vec![
+        author(1, "Ana Lopez", "ana@example.com"),
+        author(2, "ana", "ana@example.com"),
+        author(3, "Jo Smith", "jo@example.org"),
+        author(4, "jo smith", "jsmith@work.example.com"),
+        Author {
+            github_login: Some("samlee".to_string()),
+            ..author(5, "Sam Lee", "sam@example.net")
+        },
+        author(6, "sam", "12345+SamLee@users.noreply.github.com"),
+        author(7, "Christopher Park", "chris@example.com"),
+        author(8, "Christopher Prak", "cpark@example.com"),
+        // already grouped, or too short to compare loosely
+        Author {
+            person_id: Some(1),
+            ..author(9, "Lee Kim", "lee@example.com")
+        },
+        Author {
+            person_id: Some(1),
+            ..author(10, "Lee Kim", "lee@home.example.com")
+        },
+        author(11, "Bo Yu", "bo@example.com"),
+        author(12, "Bo Xu", "xu@example.com"),
+        // nothing to compare in names without letters or digits
+        author(13, "???", "unknown@example.com"),
+        author(14, "-", "nobody@example.org"),
+    ]);
+
+    let found: Vec<(i32, i32, Vec<Reason>)> = suggestions
+        .iter()
+        .map(|s| (s.from.id, s.into.id, s.reasons.clone()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (2, 1, vec![Reason::SameEmail]),
+            (6, 5, vec![Reason::SameLogin]),
+            (4, 3, vec![Reason::SameName]),
+            (8, 7, vec![Reason::SimilarName(2)]),
+        ]
+    );
+
+    assert_eq!(suggestions[0].confidence(), 90);
+    assert_eq!(
+        suggestions[0].mailmap_line(),
+        "Ana Lopez <ana@example.com> ana <ana@example.com>"
+    );
+}
@@ -0,0 +1,538 @@
+use models::{NewCommit, Project, Release};
+
+use dotenv::dotenv;
+
+use reqwest;
+use reqwest::header::Headers;
+
+use slog::Logger;
+
+use std::collections::HashSet;
+use std::env;
+use std::io::Read;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use mailmap::Mailmap;
+use policies::Policy;
+use reviews::Reviewer;
+use storage::Storage;
+
+const DEFAULT_API_URL: &'static str = "https://api.github.com";
+
+// media types for API responses and for the raw contents of files
+const JSON: &'static str = "application/vnd.github.v3+json";
+const RAW: &'static str = "application/vnd.github.v3.raw";
+
+// how many times a rate limited request is retried before giving up
+const MAX_RETRIES: u32 = 5;
+
+#[derive(Debug, Deserialize)]
+struct Object {
+    sha: String,
+    commit: CommitObject,
+    parents: Vec<Parent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Parent {
+    sha: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommitObject {
+    author: Author,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Author {
+    name: String,
+    email: String,
+}
+
+/// a commit as the API reports it
+#[derive(Debug, PartialEq)]
+pub struct GitHubCommit {
+    pub sha: String,
+    pub name: String,
+    pub email: String,
+    pub is_merge: bool,
+    /// from the `Co-authored-by:` trailers of the message
+    pub co_authors: Vec<(String, String)>,
+    /// from bors' `r=` and the `Reviewed-by:` trailers of the message
+    pub reviewers: Vec<Reviewer>,
+}
+
+/// the commits a sync found, newest first, and the ETag to send next time
+pub struct NewCommits {
+    pub commits: Vec<GitHubCommit>,
+    pub etag: Option<String>,
+}
+
+pub struct Client {
+    http: reqwest::Client,
+    api_url: String,
+    token: Option<String>,
+}
+
+impl Client {
+    pub fn new(api_url: &str, token: Option<String>) -> Client {
+        Client {
+            http: reqwest::Client::new().expect("Error creating HTTP client"),
+            api_url: api_url.trim_right_matches('/').to_string(),
+            token: token,
+        }
+    }
+
+    /// a client set up by `GITHUB_TOKEN` and `GITHUB_API_URL`
+    ///
+    /// Without a token we get the much lower anonymous rate limit.
+    pub fn from_env() -> Client {
+        dotenv().ok();
+
+        let api_url = env::var("GITHUB_API_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_string());
+        Client::new(&api_url, env::var("GITHUB_TOKEN").ok())
+    }
+
+    /// walks the commits on a branch of a repository page by page until it
+    /// reaches a known one
+    ///
+    /// `known` is asked once a page which of its commits are known.
+    ///
+    /// Passing the ETag of the last sync makes the first request conditional,
+    /// so nothing is downloaded when no commits have landed since.
+    pub fn new_commits<F>(
+        &self,
+        log: &Logger,
+        github_name: &str,
+        branch: &str,
+        etag: Option<&str>,
+        known: F,
+    ) -> NewCommits
+    where
+        F: Fn(&[&str]) -> HashSet<String>,
+    {
+        let mut url = format!(
+            "{}/repos/{}/commits?per_page=100&sha={}",
+            self.api_url, github_name, branch
+        );
+        let mut new_commits = NewCommits {
+            commits: Vec::new(),
+            etag: None,
+        };
+        let mut first_page = true;
+
+        loop {
+            // only the first page is conditional, since it's the one that
+            // changes when something lands
+            let mut resp = self.get(log, &url, JSON, if first_page { etag } else { None });
+
+            if resp.status().to_u16() == 404 {
+                panic!("GitHub doesn't know {}", github_name);
+            }
+
+            if first_page {
+                if resp.status().to_u16() == 304 {
+                    info!(log, "No new commits since the last sync");
+                    new_commits.etag = etag.map(|e| e.to_string());
+                    return new_commits;
+                }
+
+                new_commits.etag = header(resp.headers(), "ETag");
+                first_page = false;
+            }
+
+            let next = header(resp.headers(), "Link").and_then(|link| next_link(&link));
+
+            let objects: Vec<Object> = resp.json().expect("Error parsing commits from GitHub");
+            let known_shas = {
+                let shas: Vec<&str> = objects.iter().map(|object| &object.sha[..]).collect();
+                known(&shas)
+            };
+            for object in objects {
+                if known_shas.contains(&object.sha) {
+                    info!(log, "Reached known commit {}", object.sha);
+                    return new_commits;
+                }
+
+                new_commits.commits.push(GitHubCommit {
+                    sha: object.sha,
+                    name: object.commit.author.name,
+                    email: object.commit.author.email,
+                    is_merge: object.parents.len() > 1,
+                    co_authors: ::commits::co_authors(&object.commit.message),
+                    reviewers: ::reviews::reviewers(&object.commit.message),
+                });
+            }
+
+            match next {
+                Some(next) => url = next,
+                None => return new_commits,
+            }
+        }
+    }
+
+    /// the `.mailmap` on a branch of a repository, or an empty one if it has
+    /// none
+    pub fn mailmap(&self, log: &Logger, github_name: &str, branch: &str) -> Mailmap {
+        let url = format!(
+            "{}/repos/{}/contents/.mailmap?ref={}",
+            self.api_url, github_name, branch
+        );
+        let mut resp = self.get(log, &url, RAW, None);
+        if resp.status().to_u16() == 404 {
+            info!(log, "{} has no .mailmap", github_name);
+            return Mailmap::new("");
+        }
+
+        let mut contents = String::new();
+        resp.read_to_string(&mut contents)
+            .expect("Error reading the .mailmap from GitHub");
+        Mailmap::new(&contents)
+    }
+
+    /// answers with 200, 304 or, as missing files are expected, 404
+    fn get(&self, log: &Logger, url: &str, accept: &str, etag: Option<&str>) -> reqwest::Response {
+        let mut retries = 0;
+
+        loop {
+            let mut headers = Headers::new();
+            headers.set_raw("Accept", vec![accept.as_bytes().to_vec()]);
+            if let Some(ref token) = self.token {
+                headers.set_raw("Authorization", vec![format!("token {}", token).into_bytes()]);
+            }
+            if let Some(etag) = etag {
+                headers.set_raw("If-None-Match", vec![etag.as_bytes().to_vec()]);
+            }
+
+            info!(log, "Fetching {}", url);
+            let resp = self.http
+                .get(url)
+                .headers(headers)
+                .send()
+                .expect("Error talking to GitHub");
+
+            let status = resp.status().to_u16();
+            let wait = rate_limit_wait(resp.headers(), now());
+
+            match (status, wait) {
+                (200, wait) | (304, wait) | (404, wait) => {
+                    // we're out of requests, so wait for the limit to reset
+                    // rather than having the next one refused
+                    if let Some(wait) = wait {
+                        info!(log, "Rate limit reached, waiting {}s", wait.as_secs());
+                        thread::sleep(wait);
+                    }
+                    return resp;
+                }
+                (403, Some(wait)) | (429, Some(wait)) if retries < MAX_RETRIES => {
+                    info!(log, "Rate limited, retrying in {}s", wait.as_secs());
+                    thread::sleep(wait);
+                    retries += 1;
+                }
+                _ => panic!("GitHub answered {} with status {}", url, status),
+            }
+        }
+    }
+}
+
+/// adds the commits that landed on the project's branch since the last sync,
+/// using the API
+pub fn sync<S>(log: &Logger, storage: &mut S, client: &Client, project: &Project)
+where
+    S: Storage,
+{
+    let branch_release = storage
+        .release(project.id, &project.branch)
+        .expect(&format!("could not find {} release", project.branch));
+
+    let new_commits = {
+        let storage = &*storage;
+        client.new_commits(
+            log,
+            &project.github_name,
+            &project.branch,
+            project.github_etag.as_ref().map(|e| &e[..]),
+            |shas| storage.known_commits(project.id, shas),
+        )
+    };
+
+    info!(log, "Found {} new commits", new_commits.commits.len());
+
+    let policy = storage.policy(project.id);
+    save(log, storage, &branch_release, &policy, &new_commits.commits);
+
+    // only remember the ETag once the commits are saved, so a failed sync
+    // isn't skipped the next time
+    storage.set_github_etag(project, new_commits.etag.as_ref().map(|e| &e[..]));
+}
+
+/// saves commits from the API to a release, newest first like the API lists
+/// them
+pub fn save<S: Storage + ?Sized>(
+    log: &Logger,
+    storage: &mut S,
+    release: &Release,
+    policy: &Policy,
+    commits: &[GitHubCommit],
+) {
+    // oldest first, in the order they landed
+    for commit in commits.iter().rev() {
+        info!(
+            log,
+            "Creating commit {} for release {}", commit.sha, release.version
+        );
+
+        let (name, email) = storage.map_identity(&commit.name, &commit.email);
+        let author = storage.author(&name, &email);
+        let exclude = policy.excludes(commit.is_merge, &author.name, &author.email);
+        storage.add_commit(&NewCommit {
+            sha: &commit.sha,
+            release_id: release.id,
+            author_id: author.id,
+            patch_id: None,
+            is_merge: Some(commit.is_merge),
+            excluded: exclude,
+            insertions: None,
+            deletions: None,
+            files_changed: None,
+        });
+
+        let co_authors: Vec<_> = commit
+            .co_authors
+            .iter()
+            .map(|&(ref name, ref email)| {
+                let (name, email) = storage.map_identity(name, email);
+                storage.author(&name, &email)
+            })
+            .filter(|co_author| co_author.id != author.id)
+            .collect();
+        if !co_authors.is_empty() {
+            storage.add_co_authors(&commit.sha, &co_authors);
+        }
+        if !commit.reviewers.is_empty() {
+            storage.add_reviews(&commit.sha, &commit.reviewers);
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn header(headers: &Headers, name: &str) -> Option<String> {
+    headers
+        .get_raw(name)
+        .and_then(|values| values.first())
+        .and_then(|value| String::from_utf8(value.clone()).ok())
+}
+
+/// how long to wait before making another request
+///
+/// `Retry-After` is sent when we're being throttled, and the reset time is
+/// only interesting once there are no requests left.
+fn rate_limit_wait(headers: &Headers, now: u64) -> Option<Duration> {
+    if let Some(seconds) = header(headers, "Retry-After").and_then(|s| s.trim().parse().ok()) {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    match header(headers, "X-RateLimit-Remaining") {
+        Some(ref remaining) if remaining.trim() == "0" => {
+            header(headers, "X-RateLimit-Reset")
+                .and_then(|s| s.trim().parse::<u64>().ok())
+                .map(|reset| Duration::from_secs(reset.saturating_sub(now)))
+        }
+        _ => None,
+    }
+}
+
+/// the `rel="next"` URL of a `Link` header
+fn next_link(link: &str) -> Option<String> {
+    link.split(',')
+        .find(|part| part.contains("rel=\"next\""))
+        .and_then(|part| match (part.find('<'), part.find('>')) {
+            (Some(start), Some(end)) => Some(part[start + 1..end].to_string()),
+            _ => None,
+        })
+}
+
+#[test]
+fn test_github_sync() {
+    use slog;
+    use storage::Memory;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    // answers each connection with the next recorded response, and hands back
+    // the requests it got
+    fn serve(responses: Vec<&'static str>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let api = format!("http://{}", listener.local_addr().unwrap());
+        let base = api.clone();
+
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+
+                let mut request = String::new();
+                {
+                    let mut reader = BufReader::new(&stream);
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if line.trim().is_empty() {
+                            break;
+                        }
+                        request.push_str(&line);
+                    }
+                }
+                requests.push(request);
+
+                let response = response.replace("{api}", &base);
+                let split = response.find("\n\n").unwrap();
+                let (head, body) = (&response[..split], &response[split + 2..]);
+                let head = head.replace('\n', "\r\n");
+                write!(
+                    stream,
+                    "{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    head,
+                    body.len(),
+                    body
+                ).unwrap();
+            }
+
+            requests
+        });
+
+        (api, handle)
+    }
+
+    let log = slog::Logger::root(slog::Discard, o!());
+    let known = "0d2b4f6a8c1e3d5f7b9a2c4e6f8a0b1d3c5e7f9b";
+
+    // a throttled request is retried, and pages are followed until a known commit
+    let (api, server) = serve(vec![
+        include_str!("../data/github/rate-limited.http"),
+        include_str!("../data/github/commits-page-1.http"),
+        include_str!("../data/github/commits-page-2.http"),
+    ]);
+    let client = Client::new(&api, Some("s3cret".to_string()));
+    let found = client.new_commits(&log, "rust-lang/rust", "master", None, |shas| {
+        shas.iter()
+            .filter(|sha| **sha == known)
+            .map(|sha| sha.to_string())
+            .collect()
+    });
+    let requests = server.join().unwrap();
+
+    let shas: Vec<&str> = found.commits.iter().map(|c| &c.sha[..]).collect();
+    assert_eq!(
+        shas,
+        vec![
+            "e5e8e4bfb5d5c1b9d4c8e01c8a7bd0c2e1a3b2f4",
+            "9c0f7b3e2d8a4f61b5c3e7d9a1f2b4c6d8e0a2c4",
+            "3a1f9e7c5b2d8e4a6c0f1b3d5e7a9c2b4d6f8e0a",
+        ]
+    );
+    assert_eq!(found.commits[0].name, "Ana Lopez");
+    assert_eq!(found.commits[0].email, "ana@example.com");
+    assert!(!found.commits[0].is_merge);
+    assert!(found.commits[1].is_merge);
+    assert_eq!(
+        found.commits[1].reviewers,
+        vec![Reviewer::Handle("alexcrichton".to_string())]
+    );
+    assert_eq!(
+        found.commits[2].co_authors,
+        vec![("Ana Lopez".to_string(), "ana@example.com".to_string())]
+    );
+    assert_eq!(found.etag, Some("W/\"5b6a9d4c0f3e1a27\"".to_string()));
+
+    assert_eq!(requests.len(), 3);
+    assert!(requests[1].starts_with("GET /repos/rust-lang/rust/commits?per_page=100&sha=master "));
+    assert!(requests[2].starts_with("GET /repos/rust-lang/rust/commits?per_page=100&sha=master&page=2 "));
+    for request in &requests {
+        assert!(request.to_lowercase().contains("authorization: token s3cret"));
+        assert!(!request.to_lowercase().contains("if-none-match"));
+    }
+
+    // syncing saves the new commits and the ETag, with any storage
+    let mut storage = Memory::new();
+    let rust = storage.add_project("Rust", None);
+    let master = storage.add_release(rust.id, "master");
+    let old = storage.author("Old Contributor", "old@example.net");
+    storage.add_commit(&NewCommit {
+        sha: known,
+        release_id: master.id,
+        author_id: old.id,
+        patch_id: None,
+        is_merge: Some(false),
+        excluded: false,
+        insertions: None,
+        deletions: None,
+        files_changed: None,
+    });
+    let (api, server) = serve(vec![
+        include_str!("../data/github/commits-page-1.http"),
+        include_str!("../data/github/commits-page-2.http"),
+    ]);
+    sync(&log, &mut storage, &Client::new(&api, None), &rust);
+    server.join().unwrap();
+
+    let synced = storage.known_commits(rust.id, &shas);
+    assert_eq!(synced.len(), 3);
+    assert_eq!(
+        storage.project("rust").unwrap().github_etag,
+        Some("W/\"5b6a9d4c0f3e1a27\"".to_string())
+    );
+
+    // with the ETag of the last sync, an unchanged repository costs one request
+    let (api, server) = serve(vec![include_str!("../data/github/not-modified.http")]);
+    let client = Client::new(&api, None);
+    let found = client.new_commits(
+        &log,
+        "rust-lang/rust",
+        "master",
+        Some("W/\"5b6a9d4c0f3e1a27\""),
+        |_| panic!("nothing should be looked up"),
+    );
+    let requests = server.join().unwrap();
+
+    assert!(found.commits.is_empty());
+    assert_eq!(found.etag, Some("W/\"5b6a9d4c0f3e1a27\"".to_string()));
+    assert!(requests[0].to_lowercase().contains("if-none-match: w/\"5b6a9d4c0f3e1a27\""));
+    assert!(!requests[0].to_lowercase().contains("authorization"));
+
+    // projects without a local clone still get their .mailmap
+    let (api, server) = serve(vec![
+        include_str!("../data/github/mailmap.http"),
+        include_str!("../data/github/not-found.http"),
+    ]);
+    let client = Client::new(&api, None);
+    let mailmap = client.mailmap(&log, "rust-lang/rust", "master");
+    let missing = client.mailmap(&log, "rust-lang/cargo", "master");
+    let requests = server.join().unwrap();
+
+    assert_eq!(
+        mailmap.map("ana", "ana@old.example.com"),
+        ("Ana Lopez".to_string(), "ana@example.com".to_string())
+    );
+    assert_eq!(
+        missing.map("ana", "ana@old.example.com"),
+        ("ana".to_string(), "ana@old.example.com".to_string())
+    );
+    assert!(requests[0].starts_with("GET /repos/rust-lang/rust/contents/.mailmap?ref=master "));
+    assert!(requests[0].to_lowercase().contains("accept: application/vnd.github.v3.raw"));
+
+    assert_eq!(
+        next_link("<https://api.github.com/x?page=1>; rel=\"prev\", <https://api.github.com/x?page=3>; rel=\"next\""),
+        Some("https://api.github.com/x?page=3".to_string())
+    );
+    assert_eq!(next_link("<https://api.github.com/x?page=1>; rel=\"first\""), None);
+}
@@ -7,6 +7,7 @@ extern crate lazy_static;
 extern crate dotenv;
 
 extern crate regex;
+extern crate reqwest;
 extern crate semver;
 
 use diesel::prelude::*;
@@ -14,12 +15,14 @@ use diesel::pg::PgConnection;
 
 use dotenv::dotenv;
 
-extern crate caseless;
 extern crate git2;
 extern crate unicode_normalization;
 
 use std::env;
 
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
 extern crate serde_json;
 
 use serde_json::Map;
@@ -33,12 +36,31 @@ pub mod models;
 
 pub mod projects;
 pub mod releases;
+pub mod channels;
+pub mod repositories;
+pub mod submodules;
+pub mod scopes;
 pub mod commits;
 pub mod authors;
+pub mod people;
+pub mod duplicates;
 pub mod mailmap;
+pub mod opt_outs;
+pub mod policies;
+pub mod stats;
+pub mod reviews;
+pub mod versions;
+pub mod collation;
+pub mod storage;
+pub mod sync;
+pub mod github;
 
 use serde_json::value::Value;
 
+use people::{People, Shown};
+use stats::Metric;
+use storage::{Postgres, Storage};
+
 pub fn establish_connection() -> PgConnection {
     dotenv().ok();
 
@@ -47,21 +69,91 @@ pub fn establish_connection() -> PgConnection {
 }
 
 pub fn scores() -> Vec<Value> {
-    use schema::commits::dsl::*;
-    use schema::authors::dsl::*;
-    use diesel::expression::dsl::sql;
-    use diesel::types::BigInt;
+    scores_by(Metric::Commits)
+}
+
+/// the all-time scores, ranked by `metric`
+pub fn scores_by(metric: Metric) -> Vec<Value> {
+    let connection = establish_connection();
+
+    let scores = match metric {
+        Metric::Commits => commit_counts(&Postgres::new(&connection)),
+        Metric::Lines => stats::line_counts(&connection),
+        Metric::Releases => stats::release_counts(&connection),
+    };
+
+    ranked(scores, metric.key())
+}
 
+fn commit_counts<S: Storage + ?Sized>(storage: &S) -> Vec<(Shown, i64)> {
+    People::from_storage(storage).tally(storage.commit_counts())
+}
+
+/// how many anonymous people ever contributed, who the scores leave out
+pub fn anonymous_contributors() -> usize {
     let connection = establish_connection();
 
-    let scores: Vec<_> = commits
-        .inner_join(authors)
-        .filter(visible.eq(true))
-        .select((name, sql::<BigInt>("COUNT(author_id) AS author_count")))
-        .group_by((author_id, name))
-        .order(sql::<BigInt>("author_count").desc())
-        .load(&connection)
-        .unwrap();
+    anonymous_in(&Postgres::new(&connection))
+}
+
+fn anonymous_in<S: Storage + ?Sized>(storage: &S) -> usize {
+    let authors = storage.commit_counts().into_iter().map(|(author, _)| author);
+    People::from_storage(storage).anonymous(authors)
+}
+
+/// the all-time scores of a sub-project, counting the commits that touch its
+/// paths
+pub fn scoped_scores(project_name: &str) -> Option<Vec<Value>> {
+    let connection = establish_connection();
+
+    sub_project(&connection, project_name)
+        .map(|project| ranked(scopes::scores(&connection, &project), Metric::Commits.key()))
+}
+
+/// how many anonymous people contributed to a sub-project
+pub fn scoped_anonymous_contributors(project_name: &str) -> Option<usize> {
+    let connection = establish_connection();
+
+    sub_project(&connection, project_name).map(|project| scopes::anonymous(&connection, &project))
+}
+
+fn sub_project(conn: &PgConnection, project_name: &str) -> Option<models::Project> {
+    use schema::projects::dsl::*;
+
+    projects
+        .filter(name.eq(project_name))
+        .filter(parent_id.is_not_null())
+        .first(conn)
+        .ok()
+}
+
+/// the people who reviewed the most commits of a project, and the name the
+/// project is shown with
+pub fn reviewer_scores(project_name: &str) -> Option<(String, Vec<Value>)> {
+    let connection = establish_connection();
+
+    let project = match releases::find_project(&connection, project_name) {
+        Some(project) => project,
+        None => {
+            return None;
+        }
+    };
+
+    let scores = ranked(reviews::leaderboard(&connection, &project), "reviews");
+    Some((project.name, scores))
+}
+
+/// ranks people by their score, stored under `count_name`
+///
+/// People whose GitHub login we know get it too, to link to their profile.
+fn ranked(mut scores: Vec<(Shown, i64)>, count_name: &str) -> Vec<Value> {
+    // people with the same score are listed by name
+    let mut keyed: Vec<_> = scores
+        .drain(..)
+        .map(|(author, score)| (-score, collation::sort_key(&author.name), author, score))
+        .collect();
+    keyed.sort();
+    scores.extend(keyed.into_iter().map(|(_, _, author, score)| (author, score)));
 
     // these variables are used to calculate the ranking
     let mut rank = 0; // incremented every time
@@ -84,11 +176,14 @@ pub fn scores() -> Vec<Value> {
             let mut json_score: Map<String, Value> = Map::new();
 
             // we use last_rank here so that we get duplicate ranks for people
-            // with the same number of commits
+            // with the same score
             json_score.insert("rank".to_string(), Value::Number(last_rank.into()));
 
-            json_score.insert("author".to_string(), Value::String(author));
-            json_score.insert("commits".to_string(), Value::Number(score.into()));
+            if let Some(login) = author.login {
+                json_score.insert("login".to_string(), Value::String(login));
+            }
+            json_score.insert("author".to_string(), Value::String(author.name));
+            json_score.insert(count_name.to_string(), Value::Number(score.into()));
 
             Value::Object(json_score)
         })
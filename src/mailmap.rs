@@ -1,135 +1,154 @@
 use std::collections::HashMap;
-use regex::Regex;
-
-const WHITESPACE: &'static str = r#"\s*"#;
-const MATCH_NAME: &'static str = "([^<>]+)";
-const MATCH_EMAIL: &'static str = "<([^<>]+)>";
-
-// 'PNAME' =  Proper Name
-// 'PEMAIL =  Proper Email
-// 'CNAME' =  Commit Name
-// 'CEMAIL' = Commit Email
-lazy_static! {
-    static ref PNAME_CEMAIL:             Regex = Regex::new(format!(r#"^{}{} {}\s*(#.*)?$"#,       WHITESPACE, MATCH_NAME,  MATCH_EMAIL).as_str()).unwrap();
-    static ref PEMAIL_CEMAIL:            Regex = Regex::new(format!(r#"^{}{} {}\s*(#.*)?$"#,       WHITESPACE, MATCH_EMAIL, MATCH_EMAIL).as_str()).unwrap();
-    static ref PNAME_PEMAIL_CEMAIL:      Regex = Regex::new(format!(r#"^{}{} {} {}\s*(#.*)?$"#,    WHITESPACE, MATCH_NAME,  MATCH_EMAIL, MATCH_EMAIL).as_str()).unwrap();
-    static ref PNAME_PEMAIL_CNAME_CMAIL: Regex = Regex::new(format!(r#"^{}{} {} {} {}\s*(#.*)?"#, WHITESPACE, MATCH_NAME,  MATCH_EMAIL, MATCH_NAME, MATCH_EMAIL).as_str()).unwrap();
-}
 
-#[derive(Debug)]
+/// what a line of the `.mailmap` replaces; what it leaves out is kept
+#[derive(Debug, Default)]
 struct Replacement {
     name: Option<String>,
     email: Option<String>,
 }
 
-impl Replacement {
-    fn name(name: String) -> Replacement {
-        Replacement {
-            name: Some(name),
-            email: None,
-        }
-    }
-
-    fn email(email: String) -> Replacement {
-        Replacement {
-            name: None,
-            email: Some(email),
-        }
-    }
-
-    fn both(name: String, email: String) -> Replacement {
-        Replacement {
-            name: Some(name),
-            email: Some(email),
-        }
-    }
+/// the lines for one commit email
+#[derive(Debug, Default)]
+struct Entry {
+    /// from lines that only give the commit email
+    any_name: Replacement,
+    /// from lines that give the commit name too, by that name in lower case
+    by_name: HashMap<String, Replacement>,
 }
 
+/// maps the names and emails commits were made with to the ones people want to
+/// be known by, the way git reads a `.mailmap`
+///
+/// A line is a proper name and email, then optionally the name and email
+/// commits were made with, as in `Proper Name <proper@email> Commit Name
+/// <commit@email>`. Either name can be left out, and when there's one email
+/// it's the commit email. Lines that match a commit's name and email win over
+/// lines that only match its email, and otherwise later lines win.
+#[derive(Default)]
 pub struct Mailmap {
-    email_map: HashMap<String, Replacement>, // Cache 'not found' to save a lookup
-    name_email_map: HashMap<(String, String), (String, String)>,
+    /// by the commit email, in lower case
+    entries: HashMap<String, Entry>,
 }
 
 impl Mailmap {
     pub fn new(data: &str) -> Mailmap {
         let mut map = Mailmap {
-            email_map: HashMap::new(),
-            name_email_map: HashMap::new(),
+            entries: HashMap::new(),
         };
         map.parse_map(data);
         map
     }
 
     fn parse_map(&mut self, data: &str) {
-        macro_rules! capture {
-            ($cap:expr, $n:expr) => {
-                $cap.get($n).unwrap().as_str().to_owned()
+        for line in data.lines() {
+            // only a `#` at the very start makes a comment; elsewhere it's
+            // part of a name, and anything after the emails is ignored anyway
+            if line.starts_with('#') {
+                continue;
             }
-        }
 
-        for line in data.split("\n") {
-            if line.starts_with("#") {
-                continue;
+            let (proper_name, proper_email, rest) = match name_and_email(line, false) {
+                Some(found) => found,
+                None => continue,
+            };
+
+            match name_and_email(rest, true) {
+                Some((commit_name, commit_email, _)) => {
+                    self.add(proper_name, Some(proper_email), commit_name, commit_email)
+                }
+                None => self.add(proper_name, None, None, proper_email),
             }
-            if let Some(cap) = PNAME_CEMAIL.captures(line) {
-                //println!("PNAME_CEMAIL: '{}' {:?}", line, cap);
-                self.email_map.insert(
-                    capture!(cap, 2).to_lowercase(),
-                    Replacement::name(capture!(cap, 1)),
-                );
-            } else if let Some(cap) = PEMAIL_CEMAIL.captures(line) {
-                //println!("PEMAIL_CEMAIL: '{}' {:?}", line, cap);
-                self.email_map.insert(
-                    capture!(cap, 2).to_lowercase(),
-                    Replacement::email(capture!(cap, 1)),
-                );
-            } else if let Some(cap) = PNAME_PEMAIL_CEMAIL.captures(line) {
-                //println!("PNAME_PEMAIL_CEMAIL: '{}' {:?}", line, cap);
-                self.email_map.insert(
-                    capture!(cap, 3).to_lowercase(),
-                    Replacement::both(capture!(cap, 1), capture!(cap, 2)),
-                );
-            } else if let Some(cap) = PNAME_PEMAIL_CNAME_CMAIL.captures(line) {
-                //println!("PNAME_PEMAIL_CNAME_CMAIL: '{}' {:?}", line, cap);
-                self.name_email_map.insert(
-                    (
-                        capture!(cap, 3).to_lowercase(),
-                        capture!(cap, 4).to_lowercase(),
-                    ),
-                    (capture!(cap, 1), capture!(cap, 2)),
+        }
+    }
+
+    fn add(
+        &mut self,
+        proper_name: Option<&str>,
+        proper_email: Option<&str>,
+        commit_name: Option<&str>,
+        commit_email: &str,
+    ) {
+        let entry = self.entries
+            .entry(fold_case(commit_email))
+            .or_insert_with(Entry::default);
+
+        match commit_name {
+            Some(commit_name) => {
+                entry.by_name.insert(
+                    fold_case(commit_name),
+                    Replacement {
+                        name: proper_name.map(|name| name.to_owned()),
+                        email: proper_email.map(|email| email.to_owned()),
+                    },
                 );
             }
+            // lines without a commit name only replace what they give
+            None => {
+                if let Some(name) = proper_name {
+                    entry.any_name.name = Some(name.to_owned());
+                }
+                if let Some(email) = proper_email {
+                    entry.any_name.email = Some(email.to_owned());
+                }
+            }
         }
     }
 
     pub fn map(&self, name: &str, email: &str) -> (String, String) {
-        //println!("Lookup: ({}, {}) -> {:?}", name, email, self.email_map.get(email));
-        let (lower_name, lower_email) = (name.to_lowercase(), email.to_lowercase());
-        if let Some(r) = self.email_map.get(&lower_email) {
-            return (
-                r.name
-                    .as_ref()
-                    .map(|s| s.clone())
-                    .unwrap_or(name.to_owned())
-                    .clone(),
-                r.email
-                    .as_ref()
-                    .map(|s| s.clone())
-                    .unwrap_or(email.to_owned())
-                    .clone(),
-            );
-        }
+        let replacement = self.entries.get(&fold_case(email)).map(|entry| {
+            entry
+                .by_name
+                .get(&fold_case(name))
+                .unwrap_or(&entry.any_name)
+        });
 
-        if let Some(&(ref r_name, ref r_email)) = self.name_email_map
-            .get(&(lower_name.clone(), lower_email.clone()))
-        {
-            return (r_name.clone(), r_email.clone());
-        } else {
-            return (name.to_owned(), email.to_owned());
+        match replacement {
+            Some(r) => (
+                r.name.clone().unwrap_or_else(|| name.to_owned()),
+                r.email.clone().unwrap_or_else(|| email.to_owned()),
+            ),
+            None => (name.to_owned(), email.to_owned()),
         }
     }
 }
 
+/// splits `Name <email> rest` into the name, if there is one, the email and the
+/// rest
+///
+/// The email is kept as it's written, spaces and all; only the name is
+/// trimmed.
+fn name_and_email(text: &str, allow_empty_email: bool) -> Option<(Option<&str>, &str, &str)> {
+    let left = match text.find('<') {
+        Some(left) => left,
+        None => return None,
+    };
+    let right = match text[left + 1..].find('>') {
+        Some(right) => left + 1 + right,
+        None => return None,
+    };
+
+    let email = &text[left + 1..right];
+    if email.is_empty() && !allow_empty_email {
+        return None;
+    }
+
+    let name = text[..left].trim_matches(is_space);
+    let name = if name.is_empty() { None } else { Some(name) };
+
+    Some((name, email, &text[right + 1..]))
+}
+
+/// whitespace as git knows it: its `isspace` is only ASCII, and leaves out
+/// vertical tabs and form feeds
+fn is_space(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\n' || c == '\r'
+}
+
+/// git compares names and emails ignoring case, but only that of ASCII letters
+fn fold_case(s: &str) -> String {
+    s.to_ascii_lowercase()
+}
+
 #[test]
 fn test_mailmap() {
     macro_rules! check_map {
@@ -170,7 +189,7 @@ Am Valid <valid@example.com> Also Valid <alsovalid@example.com> Not Valid <notva
         "nothere@gmail.com"
     );
 
-    // PNAME_CEMAIL rule
+    // Proper Name <commit@email>
     check_map!(
         m,
         "Wrong name",
@@ -186,7 +205,7 @@ Am Valid <valid@example.com> Also Valid <alsovalid@example.com> Not Valid <notva
         "threefour@example.com"
     );
 
-    // EMAIL_CEMAIL rule
+    // <proper@email> <commit@email>
     check_map!(
         m,
         "Aaron Hill",
@@ -202,7 +221,7 @@ Am Valid <valid@example.com> Also Valid <alsovalid@example.com> Not Valid <notva
         "properemail@example.com"
     );
 
-    // PNAME_PEMAIL_CEMAIL
+    // Proper Name <proper@email> <commit@email>
     check_map!(
         m,
         "Some Person",
@@ -218,7 +237,7 @@ Am Valid <valid@example.com> Also Valid <alsovalid@example.com> Not Valid <notva
         "bobjones@example.com"
     );
 
-    // PNAME_PEMAIL_CNAME_CMAIL
+    // Proper Name <proper@email> Commit Name <commit@email>
     check_map!(
         m,
         "That Guy",
@@ -275,3 +294,36 @@ Am Valid <valid@example.com> Also Valid <alsovalid@example.com> Not Valid <notva
         "johndoe@example.com"
     );
 }
+
+/// compares `map` to what `git check-mailmap` made of the same `.mailmap`
+#[test]
+fn test_git_conformance() {
+    // split `Name <email>` like git does for the contacts it's given
+    fn contact(text: &str) -> (&str, &str) {
+        let left = text.find('<').unwrap();
+        (text[..left].trim_right(), &text[left + 1..text.len() - 1])
+    }
+
+    let mut failures = Vec::new();
+
+    for case in include_str!("../data/mailmap-test.txt").split("\n@@ ").skip(1) {
+        let (case_name, rest) = case.split_at(case.find('\n').unwrap());
+        let split = rest.find("\n--\n").unwrap();
+        let map = Mailmap::new(&rest[..split]);
+
+        for line in rest[split + 4..].lines().filter(|l| !l.is_empty()) {
+            let mut fields = line.split('\t');
+            let (name, email) = contact(fields.next().unwrap());
+            let expected = fields.next().unwrap();
+
+            let (mapped_name, mapped_email) = map.map(name, email);
+            let mapped = format!("{} <{}>", mapped_name, mapped_email);
+            if mapped != expected {
+                failures.push(format!("{}: {} => {}, not {}", case_name, line, mapped, expected));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{} contacts mapped differently from git:\n{}",
+            failures.len(), failures.join("\n"));
+}
@@ -28,6 +28,9 @@ use std::env;
 
 use serde_json::value::Value;
 
+use thanks::people::Shown;
+use thanks::stats::Metric;
+
 fn main() {
     dotenv::dotenv().ok();
 
@@ -45,6 +48,10 @@ fn main() {
 
     server.add_route("/rust/all-time", all_time);
 
+    server.add_route("/rust/all-time/lines", all_time_lines);
+
+    server.add_route("/rust/all-time/releases", all_time_releases);
+
     server.add_regex_route("/([^/]+)/(.+)", release);
 
     server.run(&addr);
@@ -84,6 +91,18 @@ fn about(_: Request) -> BoxFuture<Response, Error> {
 }
 
 fn all_time(_: Request) -> BoxFuture<Response, Error> {
+    all_time_by(Metric::Commits)
+}
+
+fn all_time_lines(_: Request) -> BoxFuture<Response, Error> {
+    all_time_by(Metric::Lines)
+}
+
+fn all_time_releases(_: Request) -> BoxFuture<Response, Error> {
+    all_time_by(Metric::Releases)
+}
+
+fn all_time_by(metric: Metric) -> BoxFuture<Response, Error> {
     let mut res = ResponseBuilder::new();
     res.with_template("all-time".to_string());
 
@@ -92,56 +111,153 @@ fn all_time(_: Request) -> BoxFuture<Response, Error> {
         Value::Bool(thanks::in_maintenance()),
     );
 
-    let scores = thanks::scores();
+    let scores = thanks::scores_by(metric);
+    let anonymous = thanks::anonymous_contributors();
+
+    res.data.insert(
+        "metric".to_string(),
+        Value::String(metric.title().to_string()),
+    );
 
     res.data.insert(
         "release".to_string(),
         Value::String(String::from("all-time")),
     );
+    // anonymous contributors count, they just aren't ranked
     res.data.insert(
         "count".to_string(),
-        Value::Number((scores.len() as u64).into()),
+        Value::Number(((scores.len() + anonymous) as u64).into()),
     );
     res.data.insert("scores".to_string(), Value::Array(scores));
+    insert_anonymous(&mut res, anonymous);
 
     res.with_status(Status::Ok);
 
     res.to_response().into_future()
 }
 
-fn release(_: &Request, cap: Captures) -> BoxFuture<Response, Error> {
+fn reviewers(project: &str) -> BoxFuture<Response, Error> {
     let mut res = ResponseBuilder::new();
-    res.with_template("release".to_string());
+    res.with_template("reviewers".to_string());
+
+    res.data.insert(
+        "maintenance".to_string(),
+        Value::Bool(thanks::in_maintenance()),
+    );
+
+    match thanks::reviewer_scores(project) {
+        Some((project_name, scores)) => {
+            res.data.insert(
+                "release".to_string(),
+                Value::String(String::from("reviewers")),
+            );
+            res.data.insert("project".to_string(), Value::String(project_name));
+            res.data.insert(
+                "count".to_string(),
+                Value::Number((scores.len() as u64).into()),
+            );
+            res.data.insert("scores".to_string(), Value::Array(scores));
+
+            res.with_status(Status::Ok);
+        }
+        None => {
+            res.with_status(Status::NotFound);
+        }
+    }
+
+    res.to_response().into_future()
+}
+
+fn scoped_all_time(project: &str) -> BoxFuture<Response, Error> {
+    let mut res = ResponseBuilder::new();
+    res.with_template("all-time".to_string());
 
     res.data.insert(
         "maintenance".to_string(),
         Value::Bool(thanks::in_maintenance()),
     );
 
+    match thanks::scoped_scores(project) {
+        Some(scores) => {
+            let anonymous = thanks::scoped_anonymous_contributors(project).unwrap_or(0);
+            res.data.insert(
+                "metric".to_string(),
+                Value::String(Metric::Commits.title().to_string()),
+            );
+            res.data.insert("scoped".to_string(), Value::Bool(true));
+            res.data.insert(
+                "release".to_string(),
+                Value::String(String::from("all-time")),
+            );
+            res.data.insert(
+                "count".to_string(),
+                Value::Number(((scores.len() + anonymous) as u64).into()),
+            );
+            res.data.insert("scores".to_string(), Value::Array(scores));
+            insert_anonymous(&mut res, anonymous);
+            res.with_status(Status::Ok);
+        }
+        None => {
+            res.with_status(Status::NotFound);
+        }
+    }
+
+    res.to_response().into_future()
+}
+
+fn release(_: &Request, cap: Captures) -> BoxFuture<Response, Error> {
     let project = cap.get(1).unwrap();
     let project = project.as_str();
 
     let release_name = cap.get(2).unwrap();
     let release_name = release_name.as_str();
 
+    // sub-projects have their own all-time page
+    if release_name == "all-time" {
+        return scoped_all_time(project);
+    }
+    if release_name == "reviewers" {
+        return reviewers(project);
+    }
+
+    let mut res = ResponseBuilder::new();
+    res.with_template("release".to_string());
+
+    res.data.insert(
+        "maintenance".to_string(),
+        Value::Bool(thanks::in_maintenance()),
+    );
+
     res.data.insert(
         "release".to_string(),
-        Value::String(release_name.to_string()),
+        Value::String(thanks::releases::title(project, release_name)),
     );
     match thanks::releases::by_version(release_name) {
         Some(v) => res.data.insert("link".to_string(), Value::String(v.link)),
         None => None,
     };
 
-    let names = thanks::releases::contributors(project, release_name);
+    let contributors = thanks::releases::contributors(project, release_name);
 
-    match names {
-        Some(names) => {
+    match contributors {
+        Some((names, anonymous)) => {
+            // anonymous contributors count, they just aren't named
             res.data.insert(
                 "count".to_string(),
-                Value::Number((names.len() as u64).into()),
+                Value::Number(((names.len() + anonymous) as u64).into()),
+            );
+            res.data.insert("names".to_string(), just_names(&names));
+            res.data.insert("linked_names".to_string(), linked(&names));
+            insert_anonymous(&mut res, anonymous);
+
+            let reviewers = thanks::releases::reviewers(project, release_name).unwrap_or_default();
+            res.data.insert(
+                "reviewer_count".to_string(),
+                Value::Number((reviewers.len() as u64).into()),
             );
-            res.data.insert("names".to_string(), Value::Array(names));
+            res.data.insert("reviewers".to_string(), just_names(&reviewers));
+            res.data.insert("linked_reviewers".to_string(), linked(&reviewers));
+
             res.with_status(Status::Ok);
         }
         None => {
@@ -151,3 +267,28 @@ fn release(_: &Request, cap: Captures) -> BoxFuture<Response, Error> {
 
     res.to_response().into_future()
 }
+
+/// how many anonymous people contributed, and whether it's just the one, so the
+/// templates get the wording right
+fn insert_anonymous(res: &mut ResponseBuilder, anonymous: usize) {
+    res.data.insert(
+        "anonymous".to_string(),
+        Value::Number((anonymous as u64).into()),
+    );
+    res.data.insert("one_anonymous".to_string(), Value::Bool(anonymous == 1));
+}
+
+/// the names alone, which is what the page data always had
+fn just_names(people: &[Shown]) -> Value {
+    Value::Array(
+        people
+            .iter()
+            .map(|shown| Value::String(shown.name.clone()))
+            .collect(),
+    )
+}
+
+/// the names with the GitHub logins to link them to
+fn linked(people: &[Shown]) -> Value {
+    Value::Array(people.iter().map(Shown::to_json).collect())
+}
@@ -1,12 +1,49 @@
-#[derive(Debug, Identifiable, Queryable, Associations)]
+use versions::VersionScheme;
+
+#[derive(Debug, Identifiable, Queryable, Associations, Clone)]
 pub struct Project {
     pub id: i32,
     pub name: String,
     pub url_path: String,
     pub github_name: String,
+    pub version_scheme: String,
+    /// a local clone to sync from instead of the GitHub API
+    pub repo_path: Option<String>,
+    /// the last commit on the branch that was synced from the local clone
+    pub last_synced: Option<String>,
+    /// the ETag of the last response from the GitHub API
+    pub github_etag: Option<String>,
+    /// whether releases also credit the commits their submodules moved by
+    pub follow_submodules: bool,
+    /// whether merge commits go uncredited
+    pub skip_merges: bool,
+    /// the project a sub-project is scoped to a part of
+    pub parent_id: Option<i32>,
+    /// the branch that's tracked, which is also the version of the
+    /// pseudo-release its commits go to
+    pub branch: String,
+    /// what the branch's pseudo-release is shown as, if not its name
+    pub branch_title: Option<String>,
 }
 
-#[derive(Debug, Identifiable, Queryable, Associations)]
+impl Project {
+    /// the name to show for the release with the given version
+    pub fn release_title<'a>(&'a self, version: &'a str) -> &'a str {
+        match self.branch_title {
+            Some(ref title) if version == self.branch => title,
+            _ => version,
+        }
+    }
+
+    pub fn version_scheme(&self) -> VersionScheme {
+        VersionScheme::from_name(&self.version_scheme).expect(&format!(
+            "Unknown version scheme {}",
+            self.version_scheme
+        ))
+    }
+}
+
+#[derive(Debug, Identifiable, Queryable, Associations, Clone)]
 #[belongs_to(Release)]
 #[belongs_to(Author)]
 #[primary_key(sha)]
@@ -14,9 +51,64 @@ pub struct Commit {
     pub sha: String,
     pub release_id: i32,
     pub author_id: i32,
+    pub patch_id: Option<String>,
+    pub backport_of: Option<String>,
+    /// the repository the commit came from, if it isn't the project's own
+    pub repository_id: Option<i32>,
+    /// unknown for commits imported before we kept track
+    pub is_merge: Option<bool>,
+    /// whether the project's policy leaves the commit uncredited
+    pub excluded: bool,
+    /// the size of the change, leaving out the project's ignored paths; these
+    /// are unknown for merges and for commits synced from the GitHub API
+    pub insertions: Option<i32>,
+    pub deletions: Option<i32>,
+    pub files_changed: Option<i32>,
 }
 
+/// someone a commit credits besides its author
 #[derive(Debug, Identifiable, Queryable, Associations)]
+#[primary_key(commit_sha, author_id)]
+#[belongs_to(Commit, foreign_key = "commit_sha")]
+#[belongs_to(Author)]
+pub struct CoAuthor {
+    pub commit_sha: String,
+    pub author_id: i32,
+}
+
+/// someone who reviewed a commit
+///
+/// `reviewer` is a handle like `alexcrichton` when it came from bors, and is
+/// resolved through the handles table, or `Name <email>` with `author_id` set
+/// when it came from a `Reviewed-by:` trailer.
+#[derive(Debug, Identifiable, Queryable, Associations)]
+#[primary_key(commit_sha, reviewer)]
+#[belongs_to(Commit, foreign_key = "commit_sha")]
+pub struct Review {
+    pub commit_sha: String,
+    pub reviewer: String,
+    pub author_id: Option<i32>,
+}
+
+/// a commit that touches the paths of a sub-project
+#[derive(Debug, Identifiable, Queryable, Associations)]
+#[primary_key(commit_sha, project_id)]
+#[belongs_to(Commit, foreign_key = "commit_sha")]
+#[belongs_to(Project)]
+pub struct CommitScope {
+    pub commit_sha: String,
+    pub project_id: i32,
+}
+
+/// the author that a reviewer handle stands for
+#[derive(Debug, Identifiable, Queryable)]
+#[primary_key(handle)]
+pub struct Handle {
+    pub handle: String,
+    pub author_id: i32,
+}
+
+#[derive(Debug, Identifiable, Queryable, Associations, Clone)]
 #[belongs_to(Project)]
 pub struct Release {
     pub id: i32,
@@ -24,14 +116,136 @@ pub struct Release {
     pub project_id: i32,
     pub visible: bool,
     pub link: String,
+    pub pseudo: bool,
+    /// seconds since the epoch of the tagged commit
+    pub released_at: Option<i64>,
 }
 
 #[derive(Debug, Identifiable, Queryable, Associations, Clone)]
+#[belongs_to(Person)]
 pub struct Author {
     pub id: i32,
     pub name: String,
     pub email: String,
     pub visible: bool,
+    /// the person this is one identity of; authors without one stand for
+    /// themselves
+    pub person_id: Option<i32>,
+    pub github_login: Option<String>,
+    /// counted, but not named
+    pub anonymous: bool,
+}
+
+/// someone who contributed under one or more identities
+#[derive(Debug, Identifiable, Queryable, Clone)]
+#[table_name = "people"]
+pub struct Person {
+    pub id: i32,
+    pub name: String,
+    /// overrides the GitHub logins of their identities
+    pub github_login: Option<String>,
+}
+
+/// the name to show for an author instead of one they no longer go by
+#[derive(Debug, Queryable, Clone)]
+pub struct NameOverride {
+    pub author_id: i32,
+    pub display_name: String,
+}
+
+/// an identity that was merged into another author
+#[derive(Debug, Identifiable, Queryable, Associations)]
+#[primary_key(name, email)]
+#[table_name = "author_aliases"]
+#[belongs_to(Author)]
+pub struct AuthorAlias {
+    pub name: String,
+    pub email: String,
+    pub author_id: i32,
+}
+
+/// a pseudo-release that follows a branch, such as beta or nightly
+#[derive(Debug, Identifiable, Queryable, Associations)]
+#[belongs_to(Project)]
+#[belongs_to(Release)]
+pub struct Channel {
+    pub id: i32,
+    pub project_id: i32,
+    pub release_id: i32,
+    pub name: String,
+    pub branch: String,
+    pub since: Option<String>,
+}
+
+/// another repository whose tags ship as part of a project's releases
+#[derive(Debug, Identifiable, Queryable, Associations)]
+#[table_name = "repositories"]
+#[belongs_to(Project)]
+pub struct SubRepository {
+    pub id: i32,
+    pub project_id: i32,
+    pub name: String,
+    pub github_name: String,
+    pub repo_path: String,
+    /// where the repository is pinned as a submodule of the project
+    pub submodule_path: Option<String>,
+    /// whether its commits are credited to the project's releases
+    pub included: bool,
+}
+
+/// an identity whose commits go uncredited, matched by regular expressions
+#[derive(Debug, Identifiable, Queryable, Associations)]
+#[belongs_to(Project)]
+pub struct Bot {
+    pub id: i32,
+    pub project_id: i32,
+    pub name_pattern: Option<String>,
+    pub email_pattern: Option<String>,
+}
+
+/// a rule that keeps someone off the pages, whichever identity they commit
+/// under next
+///
+/// Exactly one of `email`, `email_pattern`, `name` and `person_id` is set.
+#[derive(Debug, Identifiable, Queryable)]
+pub struct OptOut {
+    pub id: i32,
+    pub email: Option<String>,
+    pub email_pattern: Option<String>,
+    pub name: Option<String>,
+    pub person_id: Option<i32>,
+    pub reason: String,
+    /// seconds since the epoch
+    pub created_at: i64,
+}
+
+/// a path glob that a sub-project covers
+#[derive(Debug, Identifiable, Queryable, Associations)]
+#[belongs_to(Project)]
+pub struct ScopePath {
+    pub id: i32,
+    pub project_id: i32,
+    pub glob: String,
+}
+
+/// a path glob, like vendored or generated code, left out of line counts
+#[derive(Debug, Identifiable, Queryable, Associations)]
+#[belongs_to(Project)]
+pub struct IgnoredPath {
+    pub id: i32,
+    pub project_id: i32,
+    pub glob: String,
+}
+
+/// the tag of a repository that shipped in a release
+#[derive(Debug, Identifiable, Queryable, Associations)]
+#[belongs_to(Release)]
+#[belongs_to(SubRepository, foreign_key = "repository_id")]
+pub struct ReleaseTag {
+    pub id: i32,
+    pub release_id: i32,
+    pub repository_id: i32,
+    pub tag: String,
 }
 
 use schema::projects;
@@ -42,6 +256,11 @@ pub struct NewProject<'a> {
     pub name: &'a str,
     pub url_path: &'a str,
     pub github_name: &'a str,
+    pub version_scheme: &'a str,
+    pub repo_path: Option<&'a str>,
+    pub follow_submodules: bool,
+    pub parent_id: Option<i32>,
+    pub branch: &'a str,
 }
 
 use schema::commits;
@@ -52,6 +271,40 @@ pub struct NewCommit<'a> {
     pub sha: &'a str,
     pub release_id: i32,
     pub author_id: i32,
+    pub patch_id: Option<&'a str>,
+    pub is_merge: Option<bool>,
+    pub excluded: bool,
+    pub insertions: Option<i32>,
+    pub deletions: Option<i32>,
+    pub files_changed: Option<i32>,
+}
+
+use schema::co_authors;
+
+#[derive(Insertable)]
+#[table_name = "co_authors"]
+pub struct NewCoAuthor<'a> {
+    pub commit_sha: &'a str,
+    pub author_id: i32,
+}
+
+use schema::reviews;
+
+#[derive(Insertable)]
+#[table_name = "reviews"]
+pub struct NewReview<'a> {
+    pub commit_sha: &'a str,
+    pub reviewer: &'a str,
+    pub author_id: Option<i32>,
+}
+
+use schema::handles;
+
+#[derive(Insertable)]
+#[table_name = "handles"]
+pub struct NewHandle<'a> {
+    pub handle: &'a str,
+    pub author_id: i32,
 }
 
 use schema::releases;
@@ -63,6 +316,7 @@ pub struct NewRelease<'a> {
     pub project_id: i32,
     pub visible: bool,
     pub link: &'a str,
+    pub pseudo: bool,
 }
 
 use schema::authors;
@@ -72,6 +326,127 @@ use schema::authors;
 pub struct NewAuthor<'a> {
     pub name: &'a str,
     pub email: &'a str,
+    pub github_login: Option<&'a str>,
+}
+
+use schema::people;
+
+#[derive(Insertable)]
+#[table_name = "people"]
+pub struct NewPerson<'a> {
+    pub name: &'a str,
+}
+
+use schema::name_overrides;
+
+#[derive(Insertable)]
+#[table_name = "name_overrides"]
+pub struct NewNameOverride<'a> {
+    pub author_id: i32,
+    pub display_name: &'a str,
+}
+
+use schema::author_aliases;
+
+#[derive(Insertable)]
+#[table_name = "author_aliases"]
+pub struct NewAuthorAlias<'a> {
+    pub name: &'a str,
+    pub email: &'a str,
+    pub author_id: i32,
+}
+
+use schema::channels;
+
+#[derive(Insertable)]
+#[table_name = "channels"]
+pub struct NewChannel<'a> {
+    pub project_id: i32,
+    pub release_id: i32,
+    pub name: &'a str,
+    pub branch: &'a str,
+    pub since: Option<&'a str>,
+}
+
+use schema::channel_commits;
+
+#[derive(Insertable)]
+#[table_name = "channel_commits"]
+pub struct NewChannelCommit<'a> {
+    pub channel_id: i32,
+    pub commit_sha: &'a str,
+}
+
+use schema::repositories;
+
+#[derive(Insertable)]
+#[table_name = "repositories"]
+pub struct NewSubRepository<'a> {
+    pub project_id: i32,
+    pub name: &'a str,
+    pub github_name: &'a str,
+    pub repo_path: &'a str,
+    pub submodule_path: Option<&'a str>,
+}
+
+use schema::bots;
+
+#[derive(Insertable)]
+#[table_name = "bots"]
+pub struct NewBot<'a> {
+    pub project_id: i32,
+    pub name_pattern: Option<&'a str>,
+    pub email_pattern: Option<&'a str>,
+}
+
+use schema::opt_outs;
+
+#[derive(Insertable)]
+#[table_name = "opt_outs"]
+pub struct NewOptOut<'a> {
+    pub email: Option<&'a str>,
+    pub email_pattern: Option<&'a str>,
+    pub name: Option<&'a str>,
+    pub person_id: Option<i32>,
+    pub reason: &'a str,
+    pub created_at: i64,
+}
+
+use schema::scope_paths;
+
+#[derive(Insertable)]
+#[table_name = "scope_paths"]
+pub struct NewScopePath<'a> {
+    pub project_id: i32,
+    pub glob: &'a str,
+}
+
+use schema::ignored_paths;
+
+#[derive(Insertable)]
+#[table_name = "ignored_paths"]
+pub struct NewIgnoredPath<'a> {
+    pub project_id: i32,
+    pub glob: &'a str,
+}
+
+use schema::commit_scopes;
+
+#[derive(Insertable)]
+#[table_name = "commit_scopes"]
+pub struct NewCommitScope<'a> {
+    pub commit_sha: &'a str,
+    pub project_id: i32,
+}
+
+use schema::release_tags;
+
+#[derive(Insertable)]
+#[table_name = "release_tags"]
+pub struct NewReleaseTag<'a> {
+    pub release_id: i32,
+    pub repository_id: i32,
+    pub tag: &'a str,
 }
 
 use schema::maintenances;
@@ -0,0 +1,243 @@
+use models::{Author, NewOptOut, OptOut};
+
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use regex::Regex;
+
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// who an opt-out covers
+#[derive(Debug, PartialEq, Clone)]
+pub enum Rule {
+    Email(String),
+    /// a regular expression, matched anywhere in the email unless anchored
+    EmailPattern(String),
+    Name(String),
+    /// every identity of a person, including ones attached later
+    Person(i32),
+}
+
+/// the opt-outs in force, to tell whether an author should be hidden
+///
+/// Unlike the `visible` flag, which only covers the authors that exist when
+/// it's set, rules also cover authors created afterwards.
+pub struct OptOuts {
+    emails: HashSet<String>,
+    patterns: Vec<Regex>,
+    names: HashSet<String>,
+    people: HashSet<i32>,
+}
+
+impl OptOuts {
+    pub fn load(conn: &PgConnection) -> OptOuts {
+        use schema::opt_outs::dsl::*;
+
+        let rules: Vec<OptOut> = opt_outs.load(conn).expect("Error loading opt-outs");
+        OptOuts::new(&rules)
+    }
+
+    fn new(rules: &[OptOut]) -> OptOuts {
+        OptOuts {
+            emails: rules.iter().filter_map(|r| r.email.clone()).collect(),
+            patterns: rules
+                .iter()
+                .filter_map(|r| r.email_pattern.as_ref())
+                .map(|p| compile(p))
+                .collect(),
+            names: rules.iter().filter_map(|r| r.name.clone()).collect(),
+            people: rules.iter().filter_map(|r| r.person_id).collect(),
+        }
+    }
+
+    pub fn covers(&self, author: &Author) -> bool {
+        self.emails.contains(&author.email) || self.names.contains(&author.name)
+            || self.patterns.iter().any(|re| re.is_match(&author.email))
+            || author.person_id.map_or(false, |id| self.people.contains(&id))
+    }
+
+    /// hides the author if a rule covers them, and returns them as they are
+    /// after
+    pub fn enforce(&self, conn: &PgConnection, author: Author) -> Author {
+        use schema::authors::dsl::*;
+
+        if !author.visible || !self.covers(&author) {
+            return author;
+        }
+
+        diesel::update(authors.find(author.id))
+            .set(visible.eq(false))
+            .get_result(conn)
+            .expect("Error hiding opted-out author")
+    }
+}
+
+fn compile(pattern: &str) -> Regex {
+    Regex::new(pattern).expect(&format!("Invalid email pattern {}", pattern))
+}
+
+/// the rule as it matches stored authors, whose names and emails are
+/// normalized
+fn normalized(rule: &Rule) -> Rule {
+    match *rule {
+        Rule::Email(ref email) => Rule::Email(::authors::normalize("", email).1),
+        Rule::Name(ref name) => Rule::Name(::authors::normalize(name, "").0),
+        ref other => other.clone(),
+    }
+}
+
+pub fn all(conn: &PgConnection) -> Vec<OptOut> {
+    use schema::opt_outs::dsl::*;
+
+    opt_outs.order(id).load(conn).expect("Error loading opt-outs")
+}
+
+/// adds a rule, and hides the authors it covers already
+pub fn add(conn: &PgConnection, rule: &Rule, reason: &str) -> OptOut {
+    use schema::opt_outs;
+
+    let rule = normalized(rule);
+    if let Rule::EmailPattern(ref pattern) = rule {
+        compile(pattern);
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("The clock is before 1970")
+        .as_secs() as i64;
+
+    let new_opt_out = NewOptOut {
+        email: match rule {
+            Rule::Email(ref email) => Some(&email[..]),
+            _ => None,
+        },
+        email_pattern: match rule {
+            Rule::EmailPattern(ref pattern) => Some(&pattern[..]),
+            _ => None,
+        },
+        name: match rule {
+            Rule::Name(ref name) => Some(&name[..]),
+            _ => None,
+        },
+        person_id: match rule {
+            Rule::Person(id) => Some(id),
+            _ => None,
+        },
+        reason: reason,
+        created_at: now,
+    };
+
+    let opt_out = diesel::insert_into(opt_outs::table)
+        .values(&new_opt_out)
+        .get_result(conn)
+        .expect("Error saving opt-out");
+
+    apply(conn);
+    opt_out
+}
+
+/// removes the rules that are exactly `rule`, and shows the authors that no
+/// other rule covers again; returns how many rules there were
+///
+/// Authors hidden only by their `visible` flag stay hidden.
+pub fn remove(conn: &PgConnection, rule: &Rule) -> usize {
+    use schema::authors;
+    use schema::opt_outs::dsl::*;
+
+    let removed: Vec<OptOut> = match normalized(rule) {
+        Rule::Email(ref rule_email) => {
+            diesel::delete(opt_outs.filter(email.eq(rule_email))).get_results(conn)
+        }
+        Rule::EmailPattern(ref pattern) => {
+            diesel::delete(opt_outs.filter(email_pattern.eq(pattern))).get_results(conn)
+        }
+        Rule::Name(ref rule_name) => {
+            diesel::delete(opt_outs.filter(name.eq(rule_name))).get_results(conn)
+        }
+        Rule::Person(rule_person_id) => {
+            diesel::delete(opt_outs.filter(person_id.eq(rule_person_id))).get_results(conn)
+        }
+    }.expect("Error removing opt-out");
+
+    if removed.is_empty() {
+        return 0;
+    }
+
+    let was_covered = OptOuts::new(&removed);
+    let still_covered = OptOuts::load(conn);
+
+    let hidden: Vec<Author> = authors::table
+        .filter(authors::visible.eq(false))
+        .load(conn)
+        .expect("Error loading hidden authors");
+
+    for author in hidden {
+        if was_covered.covers(&author) && !still_covered.covers(&author) {
+            diesel::update(authors::table.find(author.id))
+                .set(authors::visible.eq(true))
+                .execute(conn)
+                .expect("Error showing author");
+        }
+    }
+
+    removed.len()
+}
+
+/// hides every author a rule covers, and returns how many were newly hidden
+pub fn apply(conn: &PgConnection) -> usize {
+    use schema::authors;
+
+    let rules = OptOuts::load(conn);
+    let shown: Vec<Author> = authors::table
+        .filter(authors::visible.eq(true))
+        .load(conn)
+        .expect("Error loading authors");
+
+    shown
+        .into_iter()
+        .filter(|author| rules.covers(author))
+        .map(|author| rules.enforce(conn, author))
+        .count()
+}
+
+#[test]
+fn test_opt_outs() {
+    let rule = |id: i32, email: Option<&str>, pattern: Option<&str>, name: Option<&str>| OptOut {
+        id: id,
+        email: email.map(|e| e.to_string()),
+        email_pattern: pattern.map(|p| p.to_string()),
+        name: name.map(|n| n.to_string()),
+        person_id: None,
+        reason: "asked on IRC".to_string(),
+        created_at: 1_500_000_000,
+    };
+    let author = |name: &str, email: &str, person_id: Option<i32>| Author {
+        id: 1,
+        name: name.to_string(),
+        email: email.to_string(),
+        visible: true,
+        person_id: person_id,
+        github_login: None,
+        anonymous: false,
+    };
+
+    let mut rules = vec![
+        rule(1, Some("ana@example.com"), None, None),
+        rule(2, None, Some(r"@jo\.example\.org$"), None),
+        rule(3, None, None, Some("Sam Lee")),
+    ];
+    rules.push(OptOut {
+        person_id: Some(7),
+        ..rule(4, None, None, None)
+    });
+    let opt_outs = OptOuts::new(&rules);
+
+    assert!(opt_outs.covers(&author("Ana Lopez", "ana@example.com", None)));
+    assert!(!opt_outs.covers(&author("Ana Lopez", "ana@work.example.com", None)));
+    assert!(opt_outs.covers(&author("Jo Smith", "anything@jo.example.org", None)));
+    assert!(opt_outs.covers(&author("Sam Lee", "sam@example.net", None)));
+    assert!(opt_outs.covers(&author("ana", "ana@new.example.com", Some(7))));
+    assert!(!opt_outs.covers(&author("ana", "ana@new.example.com", Some(8))));
+}
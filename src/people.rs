@@ -0,0 +1,449 @@
+use models::{Author, NameOverride, NewNameOverride, NewPerson, Person};
+use opt_outs::OptOuts;
+use storage::{Postgres, Storage};
+
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use serde_json::Map;
+use serde_json::value::Value;
+
+use std::collections::{HashMap, HashSet};
+
+/// who is being counted: a person, or an author that isn't attached to one
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Key {
+    Person(i32),
+    Author(i32),
+}
+
+/// someone as the pages show them: their name, and their GitHub login to
+/// link to if we know it
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct Shown {
+    pub name: String,
+    pub login: Option<String>,
+}
+
+impl Shown {
+    /// someone we only know by name, like a reviewer nobody mapped
+    pub fn unlinked(name: String) -> Shown {
+        Shown {
+            name: name,
+            login: None,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut json = Map::new();
+        if let Some(ref login) = self.login {
+            json.insert("login".to_string(), Value::String(login.clone()));
+        }
+        json.insert("name".to_string(), Value::String(self.name.clone()));
+        Value::Object(json)
+    }
+}
+
+/// who each author is, for counting and listing people rather than
+/// identities
+///
+/// A person is hidden if any of their identities opted out, and anonymous if
+/// any of them is. People are shown by their own name, and authors who aren't
+/// part of one by their name override, if they have one.
+pub struct People {
+    by_author: HashMap<i32, Key>,
+    names: HashMap<Key, String>,
+    logins: HashMap<Key, String>,
+    hidden: HashSet<Key>,
+    anonymous: HashSet<Key>,
+}
+
+impl People {
+    pub fn load(conn: &PgConnection) -> People {
+        People::from_storage(&Postgres::new(conn))
+    }
+
+    pub fn from_storage<S: Storage + ?Sized>(storage: &S) -> People {
+        People::new(storage.authors(), storage.people(), storage.name_overrides())
+    }
+
+    fn new(
+        all_authors: Vec<Author>,
+        all_people: Vec<Person>,
+        all_overrides: Vec<NameOverride>,
+    ) -> People {
+        let overrides: HashMap<i32, String> = all_overrides
+            .into_iter()
+            .map(|o| (o.author_id, o.display_name))
+            .collect();
+
+        let mut people = People {
+            by_author: HashMap::new(),
+            names: HashMap::new(),
+            logins: HashMap::new(),
+            hidden: HashSet::new(),
+            anonymous: HashSet::new(),
+        };
+
+        for person in all_people {
+            let key = Key::Person(person.id);
+            people.names.insert(key, person.name);
+            if let Some(login) = person.github_login {
+                people.logins.insert(key, login);
+            }
+        }
+
+        // a person without a login of their own takes the one of their
+        // oldest identity that has one
+        let mut all_authors = all_authors;
+        all_authors.sort_by_key(|author| author.id);
+
+        for author in all_authors {
+            let key = match author.person_id {
+                Some(id) => Key::Person(id),
+                None => {
+                    let name = overrides
+                        .get(&author.id)
+                        .cloned()
+                        .unwrap_or_else(|| author.name.clone());
+                    people.names.insert(Key::Author(author.id), name);
+                    Key::Author(author.id)
+                }
+            };
+            if !author.visible {
+                people.hidden.insert(key);
+            }
+            if author.anonymous {
+                people.anonymous.insert(key);
+            }
+            if let Some(login) = author.github_login {
+                people.logins.entry(key).or_insert(login);
+            }
+            people.by_author.insert(author.id, key);
+        }
+
+        people
+    }
+
+    /// who the author is, or `None` if they're hidden or anonymous
+    pub fn of(&self, author_id: i32) -> Option<Key> {
+        match self.by_author.get(&author_id) {
+            Some(key) if !self.hidden.contains(key) && !self.anonymous.contains(key) => {
+                Some(*key)
+            }
+            _ => None,
+        }
+    }
+
+    /// how many anonymous people are among the authors, so they can be counted
+    /// without being named
+    ///
+    /// People who opted out aren't counted at all.
+    pub fn anonymous<I>(&self, author_ids: I) -> usize
+    where
+        I: IntoIterator<Item = i32>,
+    {
+        let keys: HashSet<Key> = author_ids
+            .into_iter()
+            .filter_map(|id| self.by_author.get(&id))
+            .filter(|key| self.anonymous.contains(key) && !self.hidden.contains(key))
+            .cloned()
+            .collect();
+
+        keys.len()
+    }
+
+    pub fn name(&self, key: Key) -> &str {
+        &self.names[&key]
+    }
+
+    /// the name of the author's person, or `None` if they're hidden or
+    /// anonymous
+    pub fn name_of(&self, author_id: i32) -> Option<&str> {
+        self.of(author_id).map(|key| self.name(key))
+    }
+
+    /// the name and GitHub login of the person
+    pub fn shown(&self, key: Key) -> Shown {
+        Shown {
+            name: self.name(key).to_string(),
+            login: self.logins.get(&key).cloned(),
+        }
+    }
+
+    /// adds up scores per author into scores per person
+    pub fn tally<I>(&self, counts: I) -> Vec<(Shown, i64)>
+    where
+        I: IntoIterator<Item = (i32, i64)>,
+    {
+        let mut totals: HashMap<Key, i64> = HashMap::new();
+        for (author_id, count) in counts {
+            if let Some(key) = self.of(author_id) {
+                *totals.entry(key).or_insert(0) += count;
+            }
+        }
+
+        totals
+            .into_iter()
+            .map(|(key, total)| (self.shown(key), total))
+            .collect()
+    }
+
+    /// the names of the visible people among the authors, once each
+    pub fn names<I>(&self, author_ids: I) -> Vec<String>
+    where
+        I: IntoIterator<Item = i32>,
+    {
+        self.keys(author_ids)
+            .into_iter()
+            .map(|key| self.name(key).to_string())
+            .collect()
+    }
+
+    /// the visible people among the authors, once each, with their logins
+    pub fn shown_all<I>(&self, author_ids: I) -> Vec<Shown>
+    where
+        I: IntoIterator<Item = i32>,
+    {
+        self.keys(author_ids)
+            .into_iter()
+            .map(|key| self.shown(key))
+            .collect()
+    }
+
+    fn keys<I>(&self, author_ids: I) -> HashSet<Key>
+    where
+        I: IntoIterator<Item = i32>,
+    {
+        author_ids
+            .into_iter()
+            .filter_map(|id| self.of(id))
+            .collect()
+    }
+}
+
+pub fn find(conn: &PgConnection, person_name: &str) -> Option<Person> {
+    use schema::people::dsl::*;
+
+    people.filter(name.eq(person_name)).first(conn).ok()
+}
+
+pub fn create(conn: &PgConnection, person_name: &str) -> Person {
+    use schema::people;
+
+    diesel::insert_into(people::table)
+        .values(&NewPerson { name: person_name })
+        .get_result(conn)
+        .expect("Error saving new person")
+}
+
+/// changes the name the person is shown with
+pub fn rename(conn: &PgConnection, person: &Person, new_name: &str) -> Person {
+    use schema::people::dsl::*;
+
+    diesel::update(people.find(person.id))
+        .set(name.eq(new_name))
+        .get_result(conn)
+        .expect("Error renaming person")
+}
+
+/// makes the author one of the person's identities, taking it from any other
+/// person it belonged to
+pub fn attach(conn: &PgConnection, person: &Person, author: &Author) {
+    use schema::authors::dsl::*;
+
+    let attached = diesel::update(authors.find(author.id))
+        .set(person_id.eq(person.id))
+        .get_result(conn)
+        .expect("Error attaching identity");
+
+    // the person may have opted out
+    OptOuts::load(conn).enforce(conn, attached);
+}
+
+/// counts the author without naming them, or names them again
+pub fn set_anonymous(conn: &PgConnection, author: &Author, is_anonymous: bool) {
+    use schema::authors::dsl::*;
+
+    diesel::update(authors.find(author.id))
+        .set(anonymous.eq(is_anonymous))
+        .execute(conn)
+        .expect("Error saving anonymity");
+}
+
+/// makes the author stand for themselves again
+pub fn detach(conn: &PgConnection, author: &Author) {
+    use schema::authors::dsl::*;
+
+    diesel::update(authors.find(author.id))
+        .set(person_id.eq(None::<i32>))
+        .execute(conn)
+        .expect("Error detaching identity");
+}
+
+/// sets the GitHub login the person is linked to, over those of their
+/// identities
+pub fn set_github_login(conn: &PgConnection, person: &Person, login: Option<&str>) {
+    use schema::people::dsl::*;
+
+    diesel::update(people.find(person.id))
+        .set(github_login.eq(login))
+        .execute(conn)
+        .expect("Error saving GitHub login");
+}
+
+pub fn overrides(conn: &PgConnection) -> Vec<NameOverride> {
+    use schema::name_overrides::dsl::*;
+
+    name_overrides
+        .order(author_id)
+        .load(conn)
+        .expect("Error loading name overrides")
+}
+
+/// the overrides with the authors they're for
+pub fn overrides_with_authors(conn: &PgConnection) -> Vec<(NameOverride, Author)> {
+    use schema::{authors, name_overrides};
+
+    name_overrides::table
+        .inner_join(authors::table)
+        .order(name_overrides::author_id)
+        .load(conn)
+        .expect("Error loading name overrides")
+}
+
+/// shows the author as `new_name` everywhere, instead of the name they
+/// committed under; their own name just drops the override
+pub fn set_override(conn: &PgConnection, author: &Author, new_name: &str) {
+    use schema::name_overrides::dsl::*;
+
+    let new_name = ::authors::normalize(new_name, "").0;
+    if author.name == new_name {
+        remove_override(conn, author);
+        return;
+    }
+
+    diesel::insert_into(name_overrides)
+        .values(&NewNameOverride {
+            author_id: author.id,
+            display_name: &new_name,
+        })
+        .on_conflict(author_id)
+        .do_update()
+        .set(display_name.eq(&new_name))
+        .execute(conn)
+        .expect("Error saving name override");
+}
+
+/// shows the author by their own name again, returning whether they had an
+/// override
+pub fn remove_override(conn: &PgConnection, author: &Author) -> bool {
+    use schema::name_overrides::dsl::*;
+
+    diesel::delete(name_overrides.find(author.id))
+        .execute(conn)
+        .expect("Error removing name override") > 0
+}
+
+pub fn identities_of(conn: &PgConnection, person: &Person) -> Vec<Author> {
+    Author::belonging_to(person)
+        .load(conn)
+        .expect("Error loading identities")
+}
+
+#[test]
+fn test_people() {
+    let author = |id: i32, name: &str, visible: bool, person_id: Option<i32>| Author {
+        id: id,
+        name: name.to_string(),
+        email: format!("{}@example.com", id),
+        visible: visible,
+        person_id: person_id,
+        github_login: Some(format!("user{}", id)),
+        anonymous: false,
+    };
+
+    let people = People::new(
+        vec![
+            author(1, "Ana Lopez", true, Some(10)),
+            author(2, "ana", true, Some(10)),
+            author(3, "Jo Smith", true, None),
+            author(4, "Sam Lee", true, Some(11)),
+            author(5, "sam", false, Some(11)),
+            Author {
+                anonymous: true,
+                ..author(6, "Lee Park", true, None)
+            },
+            author(7, "Jo Smith", true, None),
+        ],
+        vec![
+            Person {
+                id: 10,
+                name: "Ana López".to_string(),
+                github_login: None,
+            },
+            Person {
+                id: 11,
+                name: "Sam Lee".to_string(),
+                github_login: Some("samlee".to_string()),
+            },
+        ],
+        vec![
+            NameOverride {
+                author_id: 3,
+                display_name: "Jo Jones".to_string(),
+            },
+        ],
+    );
+
+    // identities add up under the person's name, and hidden ones hide the
+    // whole person
+    let mut scores: Vec<_> = people
+        .tally(vec![(1, 3), (2, 2), (3, 1), (4, 7), (5, 1)])
+        .into_iter()
+        .map(|(shown, score)| (shown.name, score))
+        .collect();
+    scores.sort();
+    assert_eq!(
+        scores,
+        vec![("Ana López".to_string(), 5), ("Jo Jones".to_string(), 1)]
+    );
+
+    // overridden names don't show up anywhere
+    let mut names = people.names(vec![1, 2, 3, 4]);
+    names.sort();
+    assert_eq!(names, vec!["Ana López".to_string(), "Jo Jones".to_string()]);
+    assert_eq!(people.name_of(3), Some("Jo Jones"));
+    // someone else with the old name keeps it
+    assert_eq!(people.name_of(7), Some("Jo Smith"));
+
+    // anonymous people are counted but not named, unlike hidden ones
+    assert_eq!(people.names(vec![3, 6]), vec!["Jo Jones".to_string()]);
+    assert_eq!(people.anonymous(vec![3, 4, 6, 6]), 1);
+
+    assert_eq!(people.name_of(2), Some("Ana López"));
+    assert_eq!(people.name_of(4), None);
+
+    // people take the login of their oldest identity, and hidden or anonymous
+    // ones aren't listed
+    let mut shown = people.shown_all(vec![1, 2, 3, 4, 6]);
+    shown.sort();
+    assert_eq!(
+        shown,
+        vec![
+            Shown {
+                name: "Ana López".to_string(),
+                login: Some("user1".to_string()),
+            },
+            Shown {
+                name: "Jo Jones".to_string(),
+                login: Some("user3".to_string()),
+            },
+        ]
+    );
+    assert_eq!(
+        shown[0].to_json(),
+        ::serde_json::from_str::<Value>(r#"{"name": "Ana López", "login": "user1"}"#).unwrap()
+    );
+}
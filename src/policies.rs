@@ -0,0 +1,242 @@
+use models::{Bot, NewBot, Project};
+
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use git2::{Oid, Repository};
+
+use regex::Regex;
+
+use slog::Logger;
+
+/// which commits of a project go uncredited
+///
+/// Excluded commits are still stored, with a flag, so that changing the policy
+/// only needs `apply` instead of importing everything again.
+pub struct Policy {
+    skip_merges: bool,
+    bots: Vec<(Option<Regex>, Option<Regex>)>,
+}
+
+impl Policy {
+    pub fn for_project(conn: &PgConnection, policy_project_id: i32) -> Policy {
+        let skip_merges = {
+            use schema::projects::dsl::*;
+
+            projects
+                .find(policy_project_id)
+                .select(skip_merges)
+                .first(conn)
+                .expect("Error finding the project")
+        };
+
+        let project_bots = {
+            use schema::bots::dsl::*;
+
+            bots.filter(project_id.eq(policy_project_id))
+                .load::<Bot>(conn)
+                .expect("Error loading bots")
+        };
+
+        let patterns: Vec<_> = project_bots
+            .iter()
+            .map(|bot| {
+                (
+                    bot.name_pattern.as_ref().map(|p| &p[..]),
+                    bot.email_pattern.as_ref().map(|p| &p[..]),
+                )
+            })
+            .collect();
+
+        Policy::new(skip_merges, &patterns)
+    }
+
+    /// a policy with bots given by patterns for their name, their email, or
+    /// both
+    pub fn new(skip_merges: bool, bots: &[(Option<&str>, Option<&str>)]) -> Policy {
+        Policy {
+            skip_merges: skip_merges,
+            bots: bots.iter()
+                .map(|&(name, email)| (name.map(compile), email.map(compile)))
+                .collect(),
+        }
+    }
+
+    /// whether a commit by the (mailmapped) identity goes uncredited
+    ///
+    /// A bot matches when all of the patterns it has match.
+    pub fn excludes(&self, is_merge: bool, name: &str, email: &str) -> bool {
+        (self.skip_merges && is_merge) || self.bots.iter().any(|&(ref name_re, ref email_re)| {
+            name_re.as_ref().map_or(true, |re| re.is_match(name))
+                && email_re.as_ref().map_or(true, |re| re.is_match(email))
+        })
+    }
+}
+
+fn compile(pattern: &str) -> Regex {
+    Regex::new(pattern).expect(&format!("Invalid bot pattern {}", pattern))
+}
+
+pub fn set_skip_merges(conn: &PgConnection, project: &Project, skip: bool) {
+    use schema::projects::dsl::*;
+
+    diesel::update(projects.find(project.id))
+        .set(skip_merges.eq(skip))
+        .execute(conn)
+        .expect("Error saving merge policy");
+}
+
+pub fn add_bot(
+    conn: &PgConnection,
+    project_id: i32,
+    name_pattern: Option<&str>,
+    email_pattern: Option<&str>,
+) -> Bot {
+    use schema::bots;
+
+    // fail now rather than every time the policy is loaded
+    name_pattern.map(compile);
+    email_pattern.map(compile);
+
+    let new_bot = NewBot {
+        project_id: project_id,
+        name_pattern: name_pattern,
+        email_pattern: email_pattern,
+    };
+
+    diesel::insert_into(bots::table)
+        .values(&new_bot)
+        .get_result(conn)
+        .expect("Error saving new bot")
+}
+
+/// removes the bots with exactly these patterns, returning how many there were
+pub fn remove_bot(
+    conn: &PgConnection,
+    bot_project_id: i32,
+    name: Option<&str>,
+    email: Option<&str>,
+) -> usize {
+    use schema::bots::dsl::*;
+
+    bots.filter(project_id.eq(bot_project_id))
+        .load::<Bot>(conn)
+        .expect("Error loading bots")
+        .into_iter()
+        .filter(|bot| bot.name_pattern.as_ref().map(|p| &p[..]) == name)
+        .filter(|bot| bot.email_pattern.as_ref().map(|p| &p[..]) == email)
+        .map(|bot| {
+            diesel::delete(bots.find(bot.id))
+                .execute(conn)
+                .expect("Error removing bot")
+        })
+        .sum()
+}
+
+pub fn bots_of(conn: &PgConnection, project: &Project) -> Vec<Bot> {
+    Bot::belonging_to(project)
+        .load(conn)
+        .expect("Error loading bots")
+}
+
+/// flags the commits of a project according to its current policy
+///
+/// Commits imported before merges were recorded are looked up in the
+/// project's local clone, if it has one; without it they're taken not to be
+/// merges.
+pub fn apply(log: &Logger, conn: &PgConnection, project: &Project) {
+    use schema::{authors, commits, releases};
+    use diesel::expression::dsl::any;
+
+    let policy = Policy::for_project(conn, project.id);
+
+    let rows: Vec<(String, Option<bool>, String, String)> = commits::table
+        .inner_join(authors::table)
+        .inner_join(releases::table)
+        .filter(releases::project_id.eq(project.id))
+        .select((commits::sha, commits::is_merge, authors::name, authors::email))
+        .load(conn)
+        .expect("Error loading commits");
+
+    let repo = project
+        .repo_path
+        .as_ref()
+        .and_then(|path| Repository::open(path).ok());
+
+    let mut merges = Vec::new();
+    let mut excluded = Vec::new();
+    let mut included = Vec::new();
+
+    for (sha, is_merge, name, email) in rows {
+        let is_merge = match (is_merge, repo.as_ref()) {
+            (Some(is_merge), _) => is_merge,
+            (None, Some(repo)) => {
+                let merge = Oid::from_str(&sha)
+                    .and_then(|id| repo.find_commit(id))
+                    .map(|commit| commit.parent_count() > 1)
+                    .unwrap_or(false);
+                if merge {
+                    merges.push(sha.clone());
+                }
+                merge
+            }
+            (None, None) => false,
+        };
+
+        if policy.excludes(is_merge, &name, &email) {
+            excluded.push(sha);
+        } else {
+            included.push(sha);
+        }
+    }
+
+    info!(
+        log,
+        "Excluding {} commits of {}, crediting {}", excluded.len(), project.name, included.len()
+    );
+
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::update(commits::table.filter(commits::sha.eq(any(&merges))))
+            .set(commits::is_merge.eq(true))
+            .execute(conn)?;
+        diesel::update(commits::table.filter(commits::sha.eq(any(&excluded))))
+            .set(commits::excluded.eq(true))
+            .execute(conn)?;
+        diesel::update(commits::table.filter(commits::sha.eq(any(&included))))
+            .set(commits::excluded.eq(false))
+            .execute(conn)?;
+
+        Ok(())
+    }).expect("Error applying policy");
+}
+
+#[test]
+fn test_policy() {
+    let bors = (Some(compile("^bors$")), None);
+    let bot_email = (None, Some(compile(r"\[bot\]@users\.noreply\.github\.com$")));
+    let both = (Some(compile("^Travis")), Some(compile("@travis-ci\\.org$")));
+
+    let policy = Policy {
+        skip_merges: true,
+        bots: vec![bors, bot_email, both],
+    };
+
+    assert!(policy.excludes(true, "Alex Crichton", "alex@alexcrichton.com"));
+    assert!(!policy.excludes(false, "Alex Crichton", "alex@alexcrichton.com"));
+    assert!(policy.excludes(false, "bors", "bors@rust-lang.org"));
+    assert!(!policy.excludes(false, "borsalino", "bors@rust-lang.org"));
+    assert!(policy.excludes(
+        false,
+        "dependabot",
+        "dependabot[bot]@users.noreply.github.com"
+    ));
+    assert!(policy.excludes(false, "Travis CI", "builds@travis-ci.org"));
+    assert!(!policy.excludes(false, "Travis Cross", "tc@example.com"));
+
+    let keep_merges = Policy {
+        skip_merges: false,
+        bots: Vec::new(),
+    };
+    assert!(!keep_merges.excludes(true, "bors", "bors@rust-lang.org"));
+}
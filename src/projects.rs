@@ -4,13 +4,29 @@ use diesel;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 
-pub fn create(conn: &PgConnection, name: &str, url_path: &str, github_name: &str) -> Project {
+use versions::VersionScheme;
+
+pub fn create(
+    conn: &PgConnection,
+    name: &str,
+    url_path: &str,
+    github_name: &str,
+    version_scheme: VersionScheme,
+    repo_path: Option<&str>,
+    follow_submodules: bool,
+    branch: &str,
+) -> Project {
     use schema::projects;
 
     let new_project = NewProject {
         name: name,
         url_path: url_path,
         github_name: github_name,
+        version_scheme: version_scheme.name(),
+        repo_path: repo_path,
+        follow_submodules: follow_submodules,
+        parent_id: None,
+        branch: branch,
     };
 
     diesel::insert_into(projects::table)
@@ -18,3 +34,64 @@ pub fn create(conn: &PgConnection, name: &str, url_path: &str, github_name: &str
         .get_result(conn)
         .expect("Error saving new project")
 }
+
+/// orders the project's releases with another scheme from now on
+pub fn set_version_scheme(conn: &PgConnection, project: &Project, scheme: VersionScheme) {
+    use schema::projects::dsl::*;
+
+    diesel::update(projects.find(project.id))
+        .set(version_scheme.eq(scheme.name()))
+        .execute(conn)
+        .expect("Error saving version scheme");
+}
+
+/// remembers the last commit that was synced, so the next sync can start there
+pub fn set_last_synced(conn: &PgConnection, project: &Project, sha: &str) {
+    use schema::projects::dsl::*;
+
+    diesel::update(projects.find(project.id))
+        .set(last_synced.eq(sha))
+        .execute(conn)
+        .expect("Error saving last synced commit");
+}
+
+/// remembers the ETag of the newest commits on GitHub, so the next sync can
+/// ask whether anything changed
+pub fn set_github_etag(conn: &PgConnection, project: &Project, etag: Option<&str>) {
+    use schema::projects::dsl::*;
+
+    diesel::update(projects.find(project.id))
+        .set(github_etag.eq(etag))
+        .execute(conn)
+        .expect("Error saving GitHub ETag");
+}
+
+/// tracks another branch, and shows its pseudo-release as `title` if there is
+/// one
+///
+/// The pseudo-release of the old branch becomes the new branch's, so a renamed
+/// branch keeps its commits.
+pub fn set_branch(conn: &PgConnection, project: &Project, new_branch: &str, title: Option<&str>) {
+    use schema::projects::dsl::*;
+    use schema::releases;
+
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::update(
+            releases::table
+                .filter(releases::project_id.eq(project.id))
+                .filter(releases::version.eq(&project.branch))
+                .filter(releases::pseudo.eq(true)),
+        ).set(releases::version.eq(new_branch))
+            .execute(conn)?;
+
+        diesel::update(projects.find(project.id))
+            .set((branch.eq(new_branch), branch_title.eq(title)))
+            .execute(conn)?;
+        // sub-projects share their parent's releases
+        diesel::update(projects.filter(parent_id.eq(project.id)))
+            .set(branch.eq(new_branch))
+            .execute(conn)?;
+
+        Ok(())
+    }).expect("Error saving branch");
+}
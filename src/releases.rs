@@ -1,47 +1,32 @@
 extern crate git2;
 
 use models::*;
-use schema::*;
-
-use caseless;
 
 use diesel::*;
 use diesel::pg::PgConnection;
 
 use authors::AuthorStore;
+use collation;
+use people::{People, Shown};
+use storage::{Postgres, Storage};
 
+use serde_json::Map;
 use serde_json::value::Value;
 
-use semver::Version;
-
-use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::error::Error;
 use std::io::stderr;
 use std::str;
 
 use slog::Logger;
 
-use unicode_normalization::UnicodeNormalization;
 use releases::git2::Repository;
 use releases::git2::Oid;
+use releases::git2::ObjectType;
 
 // needed for case-insensitivity
 use diesel::types::VarChar;
 sql_function!(lower, lower_t, (x: VarChar) -> VarChar);
 
-impl Release {
-    /// provide a semver-compatible version
-    ///
-    /// rust's older versions were missing a minor version and so are not semver-compatible
-    fn semver_version(&self) -> Version {
-        Version::parse(&self.version).unwrap_or_else(|_| {
-            let v = format!("{}.0", self.version);
-            Version::parse(&v).unwrap()
-        })
-    }
-}
-
 use std::io::prelude::*;
 
 pub fn assign_commits(
@@ -52,18 +37,25 @@ pub fn assign_commits(
     commits: Vec<Oid>,
     release_project_id: i32,
 ) {
-    use diesel::pg::upsert::*;
-
-    // Could take the connection as a parameter, as problably
-    // it's already established somewhere...
-    let connection = ::establish_connection();
+    // saves through the store's connection, so that callers can assign commits
+    // as part of a transaction of their own
+    let mut storage = Postgres::borrowing_authors(cache);
+    assign_commits_in(log, repo, &mut storage, release_name, commits, release_project_id);
+}
 
+/// `assign_commits`, to any storage
+pub fn assign_commits_in<S: Storage>(
+    log: &Logger,
+    repo: &Repository,
+    storage: &mut S,
+    release_name: &str,
+    commits: Vec<Oid>,
+    release_project_id: i32,
+) {
     info!(log, "Assigning commits to release {}", release_name);
 
-    let the_release = releases::table
-        .filter(releases::version.eq(&release_name))
-        .filter(releases::project_id.eq(release_project_id))
-        .first::<Release>(&connection)
+    let the_release = storage
+        .release(release_project_id, release_name)
         .expect("could not find release");
 
     let temp_commits = commits
@@ -74,16 +66,51 @@ pub fn assign_commits(
             (commit, author)
         })
         .collect::<Vec<_>>();
-    let mut parsed_commits = Vec::new();
+    let policy = storage.policy(release_project_id);
+    let scopes = storage.scopes(release_project_id);
+    let ignored = storage.ignored_paths(release_project_id);
+    let mut shas = Vec::new();
+    let mut identities = Vec::new();
+    let mut patch_ids = Vec::new();
+    let mut flags = Vec::new();
+    let mut co_authors = Vec::new();
+    let mut reviewers = Vec::new();
+    let mut touched = Vec::new();
+    let mut line_stats = Vec::new();
 
     for &(ref commit, ref author) in temp_commits.iter() {
-        let (mapped_name, mapped_email) = cache
-            .get_mailmap()
-            .map(author.name().unwrap(), author.email().unwrap());
-        parsed_commits.push((format!("{}", commit.id()), mapped_name, mapped_email));
+        let (mapped_name, mapped_email) =
+            storage.map_identity(author.name().unwrap(), author.email().unwrap());
+
+        let mut mapped_co_authors = Vec::new();
+        for (name, email) in ::commits::co_authors(commit.message().unwrap_or("")) {
+            let co_author = storage.map_identity(&name, &email);
+            if co_author != (mapped_name.clone(), mapped_email.clone())
+                && !mapped_co_authors.contains(&co_author)
+            {
+                mapped_co_authors.push(co_author);
+            }
+        }
+        co_authors.push(mapped_co_authors);
+        reviewers.push(::reviews::reviewers(commit.message().unwrap_or("")));
+
+        // diffing is only worth it when there are sub-projects to credit
+        if scopes.is_empty() {
+            touched.push(Vec::new());
+        } else {
+            let paths = ::scopes::touched_paths(repo, commit);
+            touched.push(::scopes::touched_by(&scopes, &paths));
+        }
+
+        let is_merge = commit.parent_count() > 1;
+        flags.push((is_merge, policy.excludes(is_merge, &mapped_name, &mapped_email)));
+        shas.push(format!("{}", commit.id()));
+        identities.push((mapped_name, mapped_email));
+        patch_ids.push(::commits::patch_id(repo, commit));
+        line_stats.push(::stats::of_commit(repo, commit, &ignored));
     }
 
-    if parsed_commits.is_empty() {
+    if shas.is_empty() {
         writeln!(
             stderr(),
             "Could not find commits for {} (maybe the tag is \
@@ -94,40 +121,99 @@ pub fn assign_commits(
         return;
     }
 
-    connection
-        .transaction::<_, Box<Error>, _>(|| {
-            let by_sha = authors_by_sha(cache, parsed_commits);
-            let commits: Vec<_> = {
-                by_sha
-                    .iter()
-                    .map(|&(ref sha, author_id)| NewCommit {
-                        sha: sha.as_str(),
-                        release_id: the_release.id,
-                        author_id: author_id,
-                    })
-                    .collect()
-            };
-
-            // Set the release id of any commits that already existed
-            let inserted = insert_into(commits::table)
-                .values(&commits)
-                .on_conflict(commits::sha)
-                .do_update()
-                .set(commits::release_id.eq(the_release.id))
-                .execute(&connection)?;
-
-            if inserted == commits.len() {
-                Ok(())
-            } else {
-                Err(format!(
-                    "Expected to create or update {} commits, \
-                     but only {} were",
-                    commits.len(),
-                    inserted
-                ).into())
+    storage.atomically(|storage| {
+        let authors = storage.authors_of(&identities);
+        let commits: Vec<_> = shas.iter()
+            .zip(authors.iter())
+            .zip(patch_ids.iter())
+            .zip(flags.iter())
+            .zip(line_stats.iter())
+            .map(
+                |((((sha, author), patch_id), &(is_merge, excluded)), stats)| NewCommit {
+                    sha: sha.as_str(),
+                    release_id: the_release.id,
+                    author_id: author.id,
+                    patch_id: patch_id.as_ref().map(|id| id.as_str()),
+                    is_merge: Some(is_merge),
+                    excluded: excluded,
+                    insertions: stats.map(|s| s.insertions),
+                    deletions: stats.map(|s| s.deletions),
+                    files_changed: stats.map(|s| s.files_changed),
+                },
+            )
+            .collect();
+
+        // Set the release id of any commits that already existed
+        storage.save_commits(&commits);
+
+        for (commit, identities) in commits.iter().zip(co_authors.iter()) {
+            if !identities.is_empty() {
+                let found = storage.authors_of(identities);
+                storage.add_co_authors(commit.sha, &found);
             }
-        })
-        .expect("Error saving commits and authors");
+        }
+
+        for (commit, project_ids) in commits.iter().zip(touched.iter()) {
+            if !project_ids.is_empty() {
+                storage.add_commit_scopes(commit.sha, project_ids);
+            }
+        }
+
+        // reviews count even on commits that are excluded, like bors' merges
+        for (commit, reviewers) in commits.iter().zip(reviewers.iter()) {
+            if !reviewers.is_empty() {
+                storage.add_reviews(commit.sha, reviewers);
+            }
+        }
+
+        let new_patch_ids: Vec<&str> = commits.iter().filter_map(|c| c.patch_id).collect();
+        link_backports(storage, repo, release_project_id, &new_patch_ids);
+    });
+}
+
+/// links commits that carry the same patch as a commit in another release
+///
+/// Point releases are made of commits backported from master, so the same
+/// change ends up in the database under two different shas. The oldest copy is
+/// taken to be the original; the others point to it through `backport_of` and
+/// are credited to the original's author, so both releases thank the person
+/// who wrote the change rather than whoever did the backport.
+fn link_backports<S: Storage>(
+    storage: &mut S,
+    repo: &Repository,
+    project_id: i32,
+    patch_ids: &[&str],
+) {
+    if patch_ids.is_empty() {
+        return;
+    }
+
+    let copies = storage.commits_with_patch_ids(project_id, patch_ids);
+
+    let mut by_patch_id: HashMap<String, Vec<Commit>> = HashMap::new();
+    for commit in copies {
+        let key = commit.patch_id.clone().unwrap();
+        by_patch_id.entry(key).or_insert_with(Vec::new).push(commit);
+    }
+
+    for (_, mut copies) in by_patch_id {
+        copies.sort_by_key(|c| commit_time(repo, &c.sha));
+        let original = copies.remove(0);
+
+        // the same patch landing twice in one release is a revert and reland,
+        // not a backport
+        for copy in copies.iter().filter(|c| c.release_id != original.release_id) {
+            storage.mark_backport(&copy.sha, &original);
+        }
+    }
+}
+
+/// the committer time of a commit, used to tell an original from its backport
+fn commit_time(repo: &Repository, sha: &str) -> i64 {
+    Oid::from_str(sha)
+        .and_then(|id| repo.find_commit(id))
+        .map(|commit| commit.time().seconds())
+        .unwrap_or(i64::max_value())
 }
 
 pub fn get_first_commits(repo: &Repository, release_name: &str) -> Vec<Oid> {
@@ -157,32 +243,15 @@ pub fn get_commits(repo: &Repository, release_name: &str, previous_release: &str
         .collect()
 }
 
-type AuthorId = i32;
-
-/// Finds or creates all authors from a git log, and returns the given shas
-/// zipped with the id of the author in the database.
-fn authors_by_sha<'a>(
-    cache: &mut AuthorStore<'a>,
-    git_log: Vec<(String, String, String)>,
-) -> Vec<(String, AuthorId)> {
-    let new_authors = git_log
-        .iter()
-        .map(|&(_, ref name, ref email)| NewAuthor {
-            email: email.as_str(),
-            name: name.as_str(),
-        })
-        .collect();
-    let author_ids = cache
-        .find_or_create_all(new_authors)
-        .into_iter()
-        .map(|author| ((author.name, author.email), author.id))
-        .collect::<HashMap<_, _>>();
-    git_log
-        .iter()
-        .map(|&(ref sha, ref name, ref email)| {
-            (sha.clone(), author_ids[&(name.clone(), email.clone())])
-        })
-        .collect()
+/// returns the commits reachable from `branch` but not from `since`
+///
+/// Unlike `get_commits` this is not symmetric, since a channel only ever moves
+/// ahead of the point it started from.
+pub fn get_new_commits(repo: &Repository, branch: &str, since: &str) -> Vec<Oid> {
+    let mut walk = repo.revwalk().unwrap();
+    walk.push_range(format!("{}..{}", since, branch).as_str())
+        .unwrap();
+    walk.into_iter().map(|id| id.unwrap()).collect()
 }
 
 pub fn create(
@@ -199,6 +268,7 @@ pub fn create(
         project_id: project_id,
         visible: visible,
         link: link,
+        pseudo: false,
     };
 
     insert_into(releases::table)
@@ -207,155 +277,252 @@ pub fn create(
         .expect("Error saving new release")
 }
 
-pub fn contributors(project: &str, release_name: &str) -> Option<Vec<Value>> {
-    use schema::releases::dsl::*;
-    use schema::commits::dsl::*;
-    use models::Release;
+/// creates a release that doesn't correspond to a tag, like the branch or a channel
+///
+/// Pseudo-releases are always listed above the real releases.
+pub fn create_pseudo(conn: &PgConnection, name: &str, project_id: i32, link: &str) -> Release {
+    use schema::releases;
 
-    let connection = ::establish_connection();
+    let new_release = NewRelease {
+        version: name,
+        project_id: project_id,
+        visible: true,
+        link: link,
+        pseudo: true,
+    };
+
+    insert_into(releases::table)
+        .values(&new_release)
+        .get_result(conn)
+        .expect("Error saving new release")
+}
+
+/// returns the real releases of a project, oldest first in the order of its
+/// version scheme
+pub fn in_order(conn: &PgConnection, release_project_id: i32) -> Vec<Release> {
+    use schema::releases::dsl::*;
 
     let project = {
         use schema::projects::dsl::*;
-
-        match projects
-            .filter(lower(name).eq(lower(project)))
-            .first::<Project>(&connection)
-        {
-            Ok(p) => p,
-            Err(_) => {
-                return None;
-            }
-        }
+        projects
+            .find(release_project_id)
+            .first::<Project>(conn)
+            .expect("Error finding the project")
     };
 
-    let release: Release = match releases
-        .filter(version.eq(release_name))
-        .filter(project_id.eq(project.id))
-        .first(&connection)
+    let mut results = releases
+        .filter(project_id.eq(release_project_id))
+        .filter(pseudo.eq(false))
+        .load::<Release>(conn)
+        .expect("Error loading releases");
+
+    project.version_scheme().sort(&mut results);
+
+    results
+}
+
+/// returns the most recent real release of a project
+pub fn latest(conn: &PgConnection, release_project_id: i32) -> Option<Release> {
+    in_order(conn, release_project_id).pop()
+}
+
+/// records when a release was tagged, for projects ordered by tag date, and
+/// returns whether the tag was found
+pub fn record_date(conn: &PgConnection, repo: &Repository, release: &Release) -> bool {
+    use schema::releases::dsl::*;
+
+    let date = match repo.revparse_single(&release.version)
+        .and_then(|object| object.peel(ObjectType::Commit))
     {
-        Ok(release) => release,
+        Ok(object) => object.as_commit().unwrap().time().seconds(),
         Err(_) => {
+            return false;
+        }
+    };
+
+    update(releases.find(release.id))
+        .set(released_at.eq(date))
+        .execute(conn)
+        .expect("Error saving release date");
+    true
+}
+
+/// the people who contributed to a release, sorted by name, and how many more
+/// did so anonymously
+pub fn contributors(project: &str, release_name: &str) -> Option<(Vec<Shown>, usize)> {
+    let connection = ::establish_connection();
+
+    contributors_in(&Postgres::new(&connection), project, release_name)
+}
+
+/// `contributors`, from any storage
+pub fn contributors_in<S: Storage + ?Sized>(
+    storage: &S,
+    project_name: &str,
+    release_name: &str,
+) -> Option<(Vec<Shown>, usize)> {
+    let project = match storage.project(project_name) {
+        Some(project) => project,
+        None => {
+            return None;
+        }
+    };
+
+    // sub-projects show the releases of their parent
+    let release = match storage.release(project.parent_id.unwrap_or(project.id), release_name) {
+        Some(release) => release,
+        None => {
             return None;
         }
     };
 
+    let ids = storage.contributor_ids(&release, &project);
+
     // it'd be better to do this in the db
     // but Postgres doesn't do Unicode collation correctly on OSX
     // http://postgresql.nabble.com/Collate-order-on-Mac-OS-X-text-with-diacritics-in-UTF-8-td1912473.html
-    use schema::authors;
-    let mut names: Vec<String> = authors::table
-        .inner_join(commits)
-        .filter(release_id.eq(release.id))
-        .filter(authors::visible.eq(true))
-        .select(authors::name)
-        .distinct()
-        .load(&connection)
-        .unwrap();
-
-    inaccurate_sort(&mut names);
+    // people who contributed under several identities are thanked once
+    let people = People::from_storage(storage);
+    let anonymous = people.anonymous(ids.iter().cloned());
 
-    Some(names.into_iter().map(Value::String).collect())
+    Some((sorted(people.shown_all(ids)), anonymous))
 }
 
-// TODO: switch this out for an implementation of the Unicode Collation Algorithm
+#[deprecated(note = "sorts with the Unicode Collation Algorithm now; use `collation::sort`")]
 pub fn inaccurate_sort(strings: &mut Vec<String>) {
-    strings.sort_by(|a, b| str_cmp(&a, &b));
+    collation::sort(strings);
 }
 
-fn str_cmp(a_raw: &str, b_raw: &str) -> Ordering {
-    let a: Vec<char> = a_raw
-        .nfkd()
-        .filter(|&c| (c as u32) < 0x300 || (c as u32) > 0x36f)
-        .collect();
-    let b: Vec<char> = b_raw
-        .nfkd()
-        .filter(|&c| (c as u32) < 0x300 || (c as u32) > 0x36f)
-        .collect();
+/// the people who reviewed the commits of a release, sorted like contributors
+pub fn reviewers(project: &str, release_name: &str) -> Option<Vec<Shown>> {
+    let connection = ::establish_connection();
 
-    for (&a_char, &b_char) in a.iter().zip(b.iter()) {
-        match char_cmp(a_char, b_char) {
-            Ordering::Less => return Ordering::Less,
-            Ordering::Greater => return Ordering::Greater,
-            Ordering::Equal => {}
+    let (project, release) = match find(&connection, project, release_name) {
+        Some(found) => found,
+        None => {
+            return None;
         }
-    }
+    };
 
-    if a.len() < b.len() {
-        Ordering::Less
-    } else if a.len() > b.len() {
-        Ordering::Greater
-    } else {
-        Ordering::Equal
-    }
+    Some(sorted(::reviews::for_release(&connection, &release, &project)))
 }
 
-fn char_cmp(a_char: char, b_char: char) -> Ordering {
-    let a = caseless::default_case_fold_str(&a_char.to_string());
-    let b = caseless::default_case_fold_str(&b_char.to_string());
+/// people sorted by name, once each
+///
+/// People who share a name but not a login are both kept.
+fn sorted(people: Vec<Shown>) -> Vec<Shown> {
+    let mut keyed: Vec<_> = people
+        .into_iter()
+        .map(|shown| (collation::sort_key(&shown.name), shown))
+        .collect();
+    keyed.sort();
+    keyed.dedup();
+    keyed.into_iter().map(|(_, shown)| shown).collect()
+}
 
-    let first_char = a.chars().nth(0).unwrap_or('{');
+/// a release of a project, by the project's case-insensitive name
+///
+/// The releases of a sub-project are the ones of its parent.
+fn find(conn: &PgConnection, project: &str, release_name: &str) -> Option<(Project, Release)> {
+    use schema::releases::dsl::*;
 
-    let order = if a == b && a.len() == 1 && 'a' <= first_char && first_char <= 'z' {
-        if a_char > b_char {
-            Ordering::Less
-        } else if a_char < b_char {
-            Ordering::Greater
-        } else {
-            Ordering::Equal
+    let project = match find_project(conn, project) {
+        Some(project) => project,
+        None => {
+            return None;
         }
-    } else {
-        a.cmp(&b)
     };
 
-    order
+    releases
+        .filter(version.eq(release_name))
+        .filter(project_id.eq(project.parent_id.unwrap_or(project.id)))
+        .first(conn)
+        .ok()
+        .map(|release| (project, release))
 }
 
-/// returns all releases
+/// a project, by its case-insensitive name
+pub fn find_project(conn: &PgConnection, project_name: &str) -> Option<Project> {
+    use schema::projects::dsl::*;
+
+    projects
+        .filter(lower(name).eq(lower(project_name)))
+        .first::<Project>(conn)
+        .ok()
+}
+
+/// returns all releases, as their version and the name they're shown with
 ///
-/// sorted in semver order
+/// sorted in the order of the project's version scheme
 pub fn all() -> Vec<Value> {
-    use schema::releases::dsl::*;
-    use models::Release;
-    use models::Project;
-
     let connection = ::establish_connection();
 
-    let project = {
-        use schema::projects::dsl::*;
-        projects
-            .filter(name.eq("Rust"))
-            .first::<Project>(&connection)
-            .expect("Error finding the Rust project")
-    };
+    all_in(&Postgres::new(&connection), "Rust")
+}
 
-    let mut results = releases
-        .filter(project_id.eq(project.id))
-        .filter(visible.eq(true))
-        .load::<Release>(&connection)
-        .expect("Error loading releases");
+/// `all`, for any project and from any storage
+pub fn all_in<S: Storage + ?Sized>(storage: &S, project_name: &str) -> Vec<Value> {
+    let project = storage
+        .project(project_name)
+        .expect(&format!("Error finding the {} project", project_name));
+
+    let mut results: Vec<_> = storage
+        .releases(project.id)
+        .into_iter()
+        .filter(|r| r.visible)
+        .collect();
 
     // sort the versions
     //
-    // first we need to remove master as it is not a valid semver version, and
-    // master should be at the top anyway
-    let master = match results.iter().position(|r| r.version == "master") {
-        Some(i) => results.remove(i),
-        None => panic!("master release not found"),
-    };
+    // first we need to remove the branch and the channels as they are not
+    // valid versions, and they should be at the top anyway; a project that
+    // hasn't got its branch release yet just doesn't list it
+    let branch = results
+        .iter()
+        .position(|r| r.version == project.branch)
+        .map(|i| results.remove(i));
 
-    // next up, sort by semver version
-    results.sort_by(|a, b| a.semver_version().cmp(&b.semver_version()));
+    let (mut channels, mut results): (Vec<_>, Vec<_>) =
+        results.into_iter().partition(|r| r.pseudo);
 
-    // finally, push master/all-time back at the top
-    results.push(master);
+    // next up, sort by version
+    project.version_scheme().sort(&mut results);
+
+    // finally, push the channels and the branch back at the top
+    channels.sort_by(|a, b| b.id.cmp(&a.id));
+    results.extend(channels);
+    results.extend(branch);
 
     results
         .into_iter()
         .rev()
-        .map(|r| Value::String(r.version))
+        .map(|r| {
+            let mut release = Map::new();
+            release.insert(
+                "title".to_string(),
+                Value::String(project.release_title(&r.version).to_string()),
+            );
+            release.insert("version".to_string(), Value::String(r.version));
+            Value::Object(release)
+        })
         .collect()
 }
 
+/// the name a release of the project is shown with, which is its version
+/// unless it's the project's branch and that has a title
+pub fn title(project_name: &str, release_name: &str) -> String {
+    let connection = ::establish_connection();
+
+    // sub-projects show the releases of their parent
+    let project = find_project(&connection, project_name)
+        .map(|project| ::scopes::parent_of(&connection, &project).unwrap_or(project));
+
+    match project {
+        Some(project) => project.release_title(release_name).to_string(),
+        None => release_name.to_string(),
+    }
+}
+
 pub fn by_version(release_version: &str) -> Option<Release> {
     use schema::releases::dsl::*;
     use models::Release;
@@ -369,3 +536,105 @@ pub fn by_version(release_version: &str) -> Option<Release> {
 
     results.pop()
 }
+
+#[test]
+fn test_assign_commits() {
+    use commits::TempRepo;
+    use git2::{Signature, Time};
+    use slog;
+    use storage::Memory;
+
+    let log = Logger::root(slog::Discard, o!());
+    let repo = TempRepo::new("assign-commits");
+    let mut storage = Memory::new();
+    storage.set_mailmap("Ana Lopez <ana@example.com> <ana@old.example.com>\n");
+
+    let rust = storage.add_project("Rust", None);
+    let rustdoc = storage.add_project("rustdoc", Some(rust.id));
+    storage.add_scope_path(&rustdoc, "src/librustdoc");
+    storage.add_release(rust.id, "1.0.0");
+    storage.add_release(rust.id, "1.0.1");
+    storage.add_release(rust.id, "master");
+
+    // commits a whole tree, a second apart so originals come before backports
+    let commit = |files: &[(&str, &str)],
+                  message: &str,
+                  author: (&str, &str),
+                  seconds: i64,
+                  parents: &[&git2::Commit]| {
+        let mut root = repo.treebuilder(None).unwrap();
+        let mut librustdoc = repo.treebuilder(None).unwrap();
+        for &(path, content) in files {
+            let blob = repo.blob(content.as_bytes()).unwrap();
+            if path.starts_with("src/librustdoc/") {
+                let name = &path["src/librustdoc/".len()..];
+                librustdoc.insert(name, blob, 0o100644).unwrap();
+            } else {
+                root.insert(path, blob, 0o100644).unwrap();
+            }
+        }
+        if !librustdoc.is_empty() {
+            let mut src = repo.treebuilder(None).unwrap();
+            src.insert("librustdoc", librustdoc.write().unwrap(), 0o040000)
+                .unwrap();
+            root.insert("src", src.write().unwrap(), 0o040000).unwrap();
+        }
+        let tree = repo.find_tree(root.write().unwrap()).unwrap();
+        let sig = Signature::new(author.0, author.1, &Time::new(seconds, 0)).unwrap();
+        let id = repo.commit(None, &sig, &sig, message, &tree, parents)
+            .unwrap();
+        repo.find_commit(id).unwrap()
+    };
+
+    let start = "fn main() {\n}\n";
+    let run = "fn main() {\n    run(1);\n}\n";
+    let old_ana = ("Ana Lopez", "ana@old.example.com");
+    let base = commit(&[("main.rs", start)], "Start", old_ana, 1, &[]);
+    let fix = commit(
+        &[("main.rs", run)],
+        "Run\n\nCo-authored-by: Jo Smith <jo@example.org>\n",
+        ("Ana Lopez", "ana@example.com"),
+        2,
+        &[&base],
+    );
+    let doc = commit(
+        &[("main.rs", run), ("src/librustdoc/lib.rs", "// docs\n")],
+        "Document",
+        ("Sam Lee", "sam@example.net"),
+        3,
+        &[&fix],
+    );
+    let release_team = ("Release Team", "release@example.com");
+    let backport = commit(&[("main.rs", run)], "Run", release_team, 4, &[&base]);
+
+    let master = vec![base.id(), fix.id(), doc.id()];
+    assign_commits_in(&log, &repo, &mut storage, "master", master, rust.id);
+    assign_commits_in(&log, &repo, &mut storage, "1.0.1", vec![backport.id()], rust.id);
+
+    // a commit assigned again moves to the new release
+    assign_commits_in(&log, &repo, &mut storage, "1.0.0", vec![base.id()], rust.id);
+
+    let fix_sha = format!("{}", fix.id());
+    storage.add_channel(rust.id, "beta", &[&fix_sha]);
+
+    let names = |project: &str, release: &str| -> Vec<String> {
+        let (names, _) = contributors_in(&storage, project, release).unwrap();
+        names.into_iter().map(|shown| shown.name).collect()
+    };
+
+    assert_eq!(names("rust", "master"), vec!["Ana Lopez", "Jo Smith", "Sam Lee"]);
+    assert_eq!(names("rust", "1.0.0"), vec!["Ana Lopez"]);
+    // the backport thanks the author of the original, not whoever backported it
+    assert_eq!(names("rust", "1.0.1"), vec!["Ana Lopez"]);
+    assert_eq!(names("rust", "beta"), vec!["Ana Lopez", "Jo Smith"]);
+    assert_eq!(names("rustdoc", "master"), vec!["Sam Lee"]);
+
+    // Ana's old email is mapped to her current one
+    assert_eq!(storage.authors().len(), 4);
+
+    let versions: Vec<_> = all_in(&storage, "rust")
+        .into_iter()
+        .map(|release| release["version"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(versions, vec!["master", "beta", "1.0.1", "1.0.0"]);
+}
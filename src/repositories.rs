@@ -0,0 +1,172 @@
+use models::{NewReleaseTag, NewSubRepository, Project, Release, ReleaseTag, SubRepository};
+
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use authors::AuthorStore;
+use releases;
+
+use git2;
+
+use slog::Logger;
+
+use std::collections::HashMap;
+
+pub fn create(
+    conn: &PgConnection,
+    project_id: i32,
+    name: &str,
+    github_name: &str,
+    repo_path: &str,
+    submodule_path: Option<&str>,
+) -> SubRepository {
+    use schema::repositories;
+
+    let new_repository = NewSubRepository {
+        project_id: project_id,
+        name: name,
+        github_name: github_name,
+        repo_path: repo_path,
+        submodule_path: submodule_path,
+    };
+
+    diesel::insert_into(repositories::table)
+        .values(&new_repository)
+        .get_result(conn)
+        .expect("Error saving new repository")
+}
+
+pub fn find(
+    conn: &PgConnection,
+    project: &Project,
+    repository_name: &str,
+) -> Option<SubRepository> {
+    use schema::repositories::dsl::*;
+
+    SubRepository::belonging_to(project)
+        .filter(name.eq(repository_name))
+        .first(conn)
+        .ok()
+}
+
+/// the repository pinned at `path` as a submodule of the project
+pub fn for_submodule(conn: &PgConnection, project: &Project, path: &str) -> Option<SubRepository> {
+    use schema::repositories::dsl::*;
+
+    SubRepository::belonging_to(project)
+        .filter(submodule_path.eq(path))
+        .first(conn)
+        .ok()
+}
+
+/// sets whether the repository's commits are credited, and where its clone is
+pub fn configure(conn: &PgConnection, repository: &SubRepository, include: bool, path: &str) {
+    use schema::repositories::dsl::*;
+
+    diesel::update(repositories.find(repository.id))
+        .set((included.eq(include), repo_path.eq(path)))
+        .execute(conn)
+        .expect("Error saving repository");
+}
+
+/// marks the commits as coming from the repository, so rebuilding a release
+/// from the project's own repository leaves them alone
+pub fn claim_commits(conn: &PgConnection, repository: &SubRepository, shas: Vec<String>) {
+    use schema::commits::dsl::*;
+    use diesel::expression::dsl::any;
+
+    diesel::update(commits.filter(sha.eq(any(shas))))
+        .set(repository_id.eq(repository.id))
+        .execute(conn)
+        .expect("Error saving commit repository");
+}
+
+/// credits the commits of a repository's tag to a release of its project
+///
+/// The range starts at the tag of the closest earlier release that the
+/// repository shipped in, so releases should be mapped oldest first. The
+/// first tag that's mapped brings in the whole history up to it, just like
+/// the first release of a project does.
+pub fn import_release(
+    log: &Logger,
+    conn: &PgConnection,
+    repository: &SubRepository,
+    release: &Release,
+    tag: &str,
+) {
+    set_tag(conn, repository, release, tag);
+
+    let tags: HashMap<i32, String> = ReleaseTag::belonging_to(repository)
+        .load::<ReleaseTag>(conn)
+        .expect("Error loading release tags")
+        .into_iter()
+        .map(|t| (t.release_id, t.tag))
+        .collect();
+
+    let in_order = releases::in_order(conn, repository.project_id);
+    let position = in_order
+        .iter()
+        .position(|r| r.id == release.id)
+        .expect("can only map tags to releases, not channels");
+    let previous = in_order[..position]
+        .iter()
+        .rev()
+        .filter_map(|r| tags.get(&r.id))
+        .next();
+
+    let repo = git2::Repository::open(&repository.repo_path).unwrap();
+    let ids = match previous {
+        Some(previous) => {
+            info!(
+                log,
+                "Crediting {} {}..{} to {}", repository.name, previous, tag, release.version
+            );
+            releases::get_commits(&repo, tag, previous)
+        }
+        None => {
+            info!(
+                log,
+                "Crediting {} up to {} to {}", repository.name, tag, release.version
+            );
+            releases::get_first_commits(&repo, tag)
+        }
+    };
+    let shas: Vec<String> = ids.iter().map(|id| format!("{}", id)).collect();
+
+    let mut cache = AuthorStore::from_file(conn, &repository.repo_path);
+    releases::assign_commits(
+        log,
+        &repo,
+        &mut cache,
+        &release.version,
+        ids,
+        repository.project_id,
+    );
+
+    claim_commits(conn, repository, shas);
+}
+
+/// remembers which tag of the repository shipped in the release, replacing
+/// any earlier mapping
+fn set_tag(conn: &PgConnection, repository: &SubRepository, release: &Release, release_tag: &str) {
+    use schema::release_tags::dsl::*;
+
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::delete(
+            release_tags
+                .filter(release_id.eq(release.id))
+                .filter(repository_id.eq(repository.id)),
+        ).execute(conn)?;
+
+        diesel::insert_into(release_tags)
+            .values(&NewReleaseTag {
+                release_id: release.id,
+                repository_id: repository.id,
+                tag: release_tag,
+            })
+            .execute(conn)?;
+
+        Ok(())
+    }).expect("Error saving release tag");
+}
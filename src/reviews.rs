@@ -0,0 +1,296 @@
+use models::{Author, Handle, NewHandle, NewReview, Project, Release, Review};
+
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use authors::AuthorStore;
+use commits;
+use people::{Key, People, Shown};
+
+use regex::Regex;
+
+use std::collections::{HashMap, HashSet};
+
+lazy_static! {
+    // the first line of a bors merge, like
+    // `Auto merge of #41842 - user:branch, r=alexcrichton,eddyb`
+    static ref BORS_MERGE: Regex = Regex::new(r"^(?:Auto|Rollup) merge of .*\br=(\S+)").unwrap();
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Reviewer {
+    /// a GitHub login, which the handles table maps to an author
+    Handle(String),
+    /// a name and email, from a `Reviewed-by:` trailer
+    Identity(String, String),
+}
+
+/// the people who reviewed a commit, according to its message
+///
+/// Reviewers come from the `r=` of bors' merge commits and from
+/// `Reviewed-by:` trailers. Handles are lowercased, since GitHub logins aren't
+/// case sensitive.
+pub fn reviewers(message: &str) -> Vec<Reviewer> {
+    let mut found = Vec::new();
+
+    let first_line = message.lines().next().unwrap_or("");
+    if let Some(caps) = BORS_MERGE.captures(first_line) {
+        for handle in caps[1].split(|c| c == ',' || c == '+') {
+            let handle = handle.trim().trim_left_matches('@');
+            if !handle.is_empty() {
+                found.push(Reviewer::Handle(handle.to_lowercase()));
+            }
+        }
+    }
+
+    for (key, value) in commits::trailers(message) {
+        if key.eq_ignore_ascii_case("reviewed-by") {
+            found.push(match commits::parse_identity(&value) {
+                Some((name, email)) => Reviewer::Identity(name, email),
+                None => Reviewer::Handle(value.trim_left_matches('@').to_lowercase()),
+            });
+        }
+    }
+
+    let mut unique = Vec::new();
+    for reviewer in found {
+        if !unique.contains(&reviewer) {
+            unique.push(reviewer);
+        }
+    }
+    unique
+}
+
+/// records who reviewed a commit
+///
+/// Identities go through the mailmap and become authors right away; handles
+/// are kept as they are and resolved when they're shown, so that mapping a
+/// handle later doesn't need an import.
+pub fn record(conn: &PgConnection, cache: &mut AuthorStore, sha: &str, reviewers: &[Reviewer]) {
+    use schema::reviews;
+
+    let rows: Vec<(String, Option<i32>)> = reviewers
+        .iter()
+        .map(|reviewer| match *reviewer {
+            Reviewer::Handle(ref handle) => (handle.clone(), None),
+            Reviewer::Identity(ref name, ref email) => {
+                let (name, email) = cache.get_mailmap().map(name, email);
+                let author = cache.get(&name, &email);
+                (format!("{} <{}>", name, email), Some(author.id))
+            }
+        })
+        .collect();
+
+    let new_reviews: Vec<_> = rows.iter()
+        .map(|&(ref reviewer, author_id)| NewReview {
+            commit_sha: sha,
+            reviewer: reviewer,
+            author_id: author_id,
+        })
+        .collect();
+
+    diesel::insert_into(reviews::table)
+        .values(&new_reviews)
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .expect("Error saving reviews");
+}
+
+/// maps a reviewer handle to an author, replacing any earlier mapping
+pub fn set_handle(conn: &PgConnection, reviewer_handle: &str, author: &Author) {
+    use schema::handles::dsl::*;
+
+    let reviewer_handle = reviewer_handle.trim_left_matches('@').to_lowercase();
+
+    diesel::insert_into(handles)
+        .values(&NewHandle {
+            handle: &reviewer_handle,
+            author_id: author.id,
+        })
+        .on_conflict(handle)
+        .do_update()
+        .set(author_id.eq(author.id))
+        .execute(conn)
+        .expect("Error saving handle");
+}
+
+/// the people who reviewed the commits of a release, or of the project's part
+/// of it for sub-projects
+pub fn for_release(conn: &PgConnection, release: &Release, project: &Project) -> Vec<Shown> {
+    use schema::{commit_scopes, commits, reviews};
+    use diesel::expression::dsl::any;
+
+    let mut query = reviews::table
+        .inner_join(commits::table)
+        .select((reviews::commit_sha, reviews::reviewer, reviews::author_id))
+        .into_boxed();
+
+    query = match ::channels::commits_of(conn, release) {
+        Some(shas) => query.filter(commits::sha.eq(any(shas))),
+        None => query.filter(commits::release_id.eq(release.id)),
+    };
+
+    if project.parent_id.is_some() {
+        let scoped = commit_scopes::table
+            .filter(commit_scopes::project_id.eq(project.id))
+            .select(commit_scopes::commit_sha);
+        query = query.filter(commits::sha.eq(any(scoped)));
+    }
+
+    let release_reviews: Vec<Review> = query.load(conn).expect("Error loading reviews");
+
+    let mut shown: Vec<Shown> = resolve(conn, &release_reviews)
+        .into_iter()
+        .filter_map(|shown| shown)
+        .collect();
+    shown.sort();
+    shown.dedup();
+    shown
+}
+
+/// how many of the project's commits each reviewer reviewed
+///
+/// Reviews are counted per person, so the handle and the identities of
+/// someone add up, and people who share a name are kept apart. Sub-projects
+/// count the reviews of the commits that touch their paths.
+pub fn leaderboard(conn: &PgConnection, project: &Project) -> Vec<(Shown, i64)> {
+    use schema::{commit_scopes, commits, releases, reviews};
+    use diesel::expression::dsl::any;
+
+    let mut query = reviews::table
+        .inner_join(commits::table.inner_join(releases::table))
+        .select((reviews::commit_sha, reviews::reviewer, reviews::author_id))
+        .into_boxed();
+
+    // the commits of sub-projects are in their parent's releases
+    query = if project.parent_id.is_some() {
+        let scoped = commit_scopes::table
+            .filter(commit_scopes::project_id.eq(project.id))
+            .select(commit_scopes::commit_sha);
+        query.filter(commits::sha.eq(any(scoped)))
+    } else {
+        query.filter(releases::project_id.eq(project.id))
+    };
+
+    let project_reviews: Vec<Review> = query.load(conn).expect("Error loading reviews");
+
+    let people = People::load(conn);
+    let mut by_person: HashSet<(&str, Key)> = HashSet::new();
+    let mut by_name: HashSet<(&str, String)> = HashSet::new();
+    for (review, id) in project_reviews.iter().zip(reviewer_ids(conn, &project_reviews)) {
+        match id {
+            Some(id) => {
+                if let Some(key) = people.of(id) {
+                    by_person.insert((&review.commit_sha, key));
+                }
+            }
+            None => {
+                by_name.insert((&review.commit_sha, unmapped_name(review)));
+            }
+        }
+    }
+
+    let mut counts: HashMap<Key, i64> = HashMap::new();
+    for (_, key) in by_person {
+        *counts.entry(key).or_insert(0) += 1;
+    }
+    let mut unmapped: HashMap<String, i64> = HashMap::new();
+    for (_, name) in by_name {
+        *unmapped.entry(name).or_insert(0) += 1;
+    }
+
+    counts
+        .into_iter()
+        .map(|(key, count)| (people.shown(key), count))
+        .chain(unmapped.into_iter().map(|(name, count)| (Shown::unlinked(name), count)))
+        .collect()
+}
+
+/// who to show for each review, or `None` for people who opted out
+///
+/// Handles nobody mapped are shown as they are.
+fn resolve(conn: &PgConnection, reviews: &[Review]) -> Vec<Option<Shown>> {
+    let people = People::load(conn);
+
+    reviews
+        .iter()
+        .zip(reviewer_ids(conn, reviews))
+        .map(|(review, id)| match id {
+            Some(id) => people.of(id).map(|key| people.shown(key)),
+            None => Some(Shown::unlinked(unmapped_name(review))),
+        })
+        .collect()
+}
+
+/// the author of each review, if the reviewer was mapped to one
+fn reviewer_ids(conn: &PgConnection, reviews: &[Review]) -> Vec<Option<i32>> {
+    use schema::{authors, handles};
+
+    // authors whose GitHub login we know are found by it too, unless the
+    // handle was mapped by hand
+    let mut handle_map: HashMap<String, i32> = authors::table
+        .filter(authors::github_login.is_not_null())
+        .select((authors::github_login, authors::id))
+        .load::<(Option<String>, i32)>(conn)
+        .expect("Error loading GitHub logins")
+        .into_iter()
+        .filter_map(|(login, id)| login.map(|login| (login.to_lowercase(), id)))
+        .collect();
+    handle_map.extend(
+        handles::table
+            .load::<Handle>(conn)
+            .expect("Error loading handles")
+            .into_iter()
+            .map(|h| (h.handle, h.author_id)),
+    );
+
+    reviews
+        .iter()
+        .map(|review| {
+            review
+                .author_id
+                .or_else(|| handle_map.get(&review.reviewer).cloned())
+        })
+        .collect()
+}
+
+/// the name of a reviewer nobody mapped to an author
+///
+/// An identity whose author is gone still shouldn't show its email.
+fn unmapped_name(review: &Review) -> String {
+    let name = commits::parse_identity(&review.reviewer)
+        .map(|(name, _)| name)
+        .unwrap_or_else(|| review.reviewer.clone());
+    ::authors::normalize(&name, "").0
+}
+
+#[test]
+fn test_reviewers() {
+    use self::Reviewer::*;
+
+    assert_eq!(
+        reviewers("Auto merge of #41842 - ana:fix-typo, r=alexcrichton,@eddyb\n\nFix typo"),
+        vec![Handle("alexcrichton".to_string()), Handle("eddyb".to_string())]
+    );
+    assert_eq!(
+        reviewers("Rollup merge of #41787 - jo:test, r=SteveKlabnik\n\nAdd regression test"),
+        vec![Handle("steveklabnik".to_string())]
+    );
+
+    // only bors' merges are looked at, not any commit that mentions r=
+    assert!(reviewers("Set r=1 in the config").is_empty());
+
+    assert_eq!(
+        reviewers(
+            "Fix the thing\n\n\
+             Reviewed-by: Ana Lopez <ana@example.com>\n\
+             Reviewed-by: @nrc\n\
+             Reviewed-by: Ana Lopez <ana@example.com>"
+        ),
+        vec![
+            Identity("Ana Lopez".to_string(), "ana@example.com".to_string()),
+            Handle("nrc".to_string()),
+        ]
+    );
+}
@@ -1,9 +1,61 @@
+table! {
+    author_aliases (name, email) {
+        name -> Varchar,
+        email -> Varchar,
+        author_id -> Int4,
+    }
+}
+
 table! {
     authors (id) {
         id -> Int4,
         name -> Varchar,
         email -> Varchar,
         visible -> Bool,
+        person_id -> Nullable<Int4>,
+        github_login -> Nullable<Varchar>,
+        anonymous -> Bool,
+    }
+}
+
+table! {
+    bots (id) {
+        id -> Int4,
+        project_id -> Int4,
+        name_pattern -> Nullable<Varchar>,
+        email_pattern -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    channel_commits (channel_id, commit_sha) {
+        channel_id -> Int4,
+        commit_sha -> Varchar,
+    }
+}
+
+table! {
+    channels (id) {
+        id -> Int4,
+        project_id -> Int4,
+        release_id -> Int4,
+        name -> Varchar,
+        branch -> Varchar,
+        since -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    commit_scopes (commit_sha, project_id) {
+        commit_sha -> Varchar,
+        project_id -> Int4,
+    }
+}
+
+table! {
+    co_authors (commit_sha, author_id) {
+        commit_sha -> Varchar,
+        author_id -> Int4,
     }
 }
 
@@ -12,6 +64,29 @@ table! {
         sha -> Varchar,
         release_id -> Int4,
         author_id -> Int4,
+        patch_id -> Nullable<Varchar>,
+        backport_of -> Nullable<Varchar>,
+        repository_id -> Nullable<Int4>,
+        is_merge -> Nullable<Bool>,
+        excluded -> Bool,
+        insertions -> Nullable<Int4>,
+        deletions -> Nullable<Int4>,
+        files_changed -> Nullable<Int4>,
+    }
+}
+
+table! {
+    handles (handle) {
+        handle -> Varchar,
+        author_id -> Int4,
+    }
+}
+
+table! {
+    ignored_paths (id) {
+        id -> Int4,
+        project_id -> Int4,
+        glob -> Varchar,
     }
 }
 
@@ -22,12 +97,57 @@ table! {
     }
 }
 
+table! {
+    name_overrides (author_id) {
+        author_id -> Int4,
+        display_name -> Varchar,
+    }
+}
+
+table! {
+    opt_outs (id) {
+        id -> Int4,
+        email -> Nullable<Varchar>,
+        email_pattern -> Nullable<Varchar>,
+        name -> Nullable<Varchar>,
+        person_id -> Nullable<Int4>,
+        reason -> Varchar,
+        created_at -> Int8,
+    }
+}
+
+table! {
+    people (id) {
+        id -> Int4,
+        name -> Varchar,
+        github_login -> Nullable<Varchar>,
+    }
+}
+
 table! {
     projects (id) {
         id -> Int4,
         name -> Varchar,
         url_path -> Varchar,
         github_name -> Varchar,
+        version_scheme -> Varchar,
+        repo_path -> Nullable<Varchar>,
+        last_synced -> Nullable<Varchar>,
+        github_etag -> Nullable<Varchar>,
+        follow_submodules -> Bool,
+        skip_merges -> Bool,
+        parent_id -> Nullable<Int4>,
+        branch -> Varchar,
+        branch_title -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    release_tags (id) {
+        id -> Int4,
+        release_id -> Int4,
+        repository_id -> Int4,
+        tag -> Varchar,
     }
 }
 
@@ -38,16 +158,83 @@ table! {
         project_id -> Int4,
         visible -> Bool,
         link -> Varchar,
+        pseudo -> Bool,
+        released_at -> Nullable<Int8>,
+    }
+}
+
+table! {
+    reviews (commit_sha, reviewer) {
+        commit_sha -> Varchar,
+        reviewer -> Varchar,
+        author_id -> Nullable<Int4>,
+    }
+}
+
+table! {
+    repositories (id) {
+        id -> Int4,
+        project_id -> Int4,
+        name -> Varchar,
+        github_name -> Varchar,
+        repo_path -> Varchar,
+        submodule_path -> Nullable<Varchar>,
+        included -> Bool,
+    }
+}
+
+table! {
+    scope_paths (id) {
+        id -> Int4,
+        project_id -> Int4,
+        glob -> Varchar,
     }
 }
 
+joinable!(author_aliases -> authors (author_id));
+joinable!(authors -> people (person_id));
+joinable!(bots -> projects (project_id));
+joinable!(channel_commits -> channels (channel_id));
+joinable!(channel_commits -> commits (commit_sha));
+joinable!(channels -> projects (project_id));
+joinable!(channels -> releases (release_id));
+joinable!(commit_scopes -> commits (commit_sha));
+joinable!(commit_scopes -> projects (project_id));
+joinable!(co_authors -> authors (author_id));
+joinable!(co_authors -> commits (commit_sha));
 joinable!(commits -> authors (author_id));
+joinable!(commits -> releases (release_id));
+joinable!(commits -> repositories (repository_id));
+joinable!(release_tags -> releases (release_id));
+joinable!(release_tags -> repositories (repository_id));
 joinable!(releases -> projects (project_id));
+joinable!(repositories -> projects (project_id));
+joinable!(reviews -> commits (commit_sha));
+joinable!(handles -> authors (author_id));
+joinable!(ignored_paths -> projects (project_id));
+joinable!(name_overrides -> authors (author_id));
+joinable!(opt_outs -> people (person_id));
+joinable!(scope_paths -> projects (project_id));
 
 allow_tables_to_appear_in_same_query!(
+    author_aliases,
     authors,
+    bots,
+    channel_commits,
+    channels,
+    co_authors,
+    commit_scopes,
     commits,
+    handles,
+    ignored_paths,
     maintenances,
+    name_overrides,
+    opt_outs,
+    people,
     projects,
+    release_tags,
     releases,
+    repositories,
+    reviews,
+    scope_paths,
 );
\ No newline at end of file
@@ -0,0 +1,342 @@
+use models::{NewCommitScope, NewProject, NewScopePath, Project, ScopePath};
+
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use git2::{Commit, Oid, Repository};
+
+use regex::{self, Regex};
+
+use people::{People, Shown};
+
+use slog::Logger;
+
+use stats;
+
+use std::collections::HashMap;
+
+/// the paths of a sub-project, like `src/librustdoc` for rustdoc
+///
+/// Sub-projects have no releases of their own. They show the releases of
+/// their parent, narrowed down to the commits that touch their paths.
+pub struct Scope {
+    pub project_id: i32,
+    globs: Vec<Regex>,
+}
+
+impl Scope {
+    pub fn new(project_id: i32, globs: &[&str]) -> Scope {
+        Scope {
+            project_id: project_id,
+            globs: globs.iter().map(|g| compile(g)).collect(),
+        }
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        self.globs.iter().any(|re| re.is_match(path))
+    }
+}
+
+/// turns a path glob into a regular expression
+///
+/// `*` and `?` stay within a directory and `**` crosses any number of them. A
+/// glob that matches a directory covers everything below it, unless it ends in
+/// a pattern for files, like `*.rs`, which only matches files.
+pub fn compile(glob: &str) -> Regex {
+    let is_dir = glob.ends_with('/');
+    let glob = glob.trim_matches('/');
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    pattern.push_str("(?:.*/)?");
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    let last = glob.rsplit('/').next().unwrap_or("");
+    let is_file_pattern = last.contains(|c| c == '*' || c == '?') && last.contains('.');
+    if is_dir || !is_file_pattern {
+        pattern.push_str("(?:/.*)?");
+    }
+    pattern.push('$');
+
+    Regex::new(&pattern).expect(&format!("Invalid path glob {}", glob))
+}
+
+/// the scopes of the sub-projects of a project
+pub fn for_project(conn: &PgConnection, parent: i32) -> Vec<Scope> {
+    use schema::{projects, scope_paths};
+
+    let rows: Vec<(i32, String)> = scope_paths::table
+        .inner_join(projects::table)
+        .filter(projects::parent_id.eq(parent))
+        .select((scope_paths::project_id, scope_paths::glob))
+        .load(conn)
+        .expect("Error loading sub-project paths");
+
+    let mut globs: HashMap<i32, Vec<String>> = HashMap::new();
+    for (project_id, glob) in rows {
+        globs.entry(project_id).or_insert_with(Vec::new).push(glob);
+    }
+
+    globs
+        .into_iter()
+        .map(|(project_id, globs)| {
+            let globs: Vec<&str> = globs.iter().map(|g| &g[..]).collect();
+            Scope::new(project_id, &globs)
+        })
+        .collect()
+}
+
+/// creates a sub-project, which shares its parent's repository and releases
+pub fn create(conn: &PgConnection, parent: &Project, name: &str) -> Project {
+    use schema::projects;
+
+    let new_project = NewProject {
+        name: name,
+        url_path: &parent.url_path,
+        github_name: &parent.github_name,
+        version_scheme: &parent.version_scheme,
+        repo_path: None,
+        follow_submodules: false,
+        parent_id: Some(parent.id),
+        branch: &parent.branch,
+    };
+
+    diesel::insert_into(projects::table)
+        .values(&new_project)
+        .get_result(conn)
+        .expect("Error saving new sub-project")
+}
+
+/// the project a sub-project is part of
+pub fn parent_of(conn: &PgConnection, project: &Project) -> Option<Project> {
+    use schema::projects::dsl::*;
+
+    project
+        .parent_id
+        .map(|parent| projects.find(parent).first(conn).expect("Error finding the parent project"))
+}
+
+/// replaces the paths of a sub-project
+pub fn set_globs(conn: &PgConnection, project: &Project, globs: &[&str]) {
+    use schema::scope_paths::dsl::*;
+
+    // fail now rather than on the next import
+    for g in globs {
+        compile(g);
+    }
+
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::delete(scope_paths.filter(project_id.eq(project.id))).execute(conn)?;
+
+        let new_globs: Vec<_> = globs
+            .iter()
+            .map(|g| NewScopePath {
+                project_id: project.id,
+                glob: g,
+            })
+            .collect();
+        diesel::insert_into(scope_paths)
+            .values(&new_globs)
+            .execute(conn)?;
+
+        Ok(())
+    }).expect("Error saving sub-project paths");
+}
+
+pub fn globs_of(conn: &PgConnection, project: &Project) -> Vec<ScopePath> {
+    ScopePath::belonging_to(project)
+        .load(conn)
+        .expect("Error loading sub-project paths")
+}
+
+/// the paths a commit changes, compared to its first parent
+///
+/// For a merge that's everything the merged branch brought in.
+pub fn touched_paths(repo: &Repository, commit: &Commit) -> Vec<String> {
+    let diff = match stats::first_parent_diff(repo, commit) {
+        Some(diff) => diff,
+        None => return Vec::new(),
+    };
+
+    let mut paths: Vec<String> = diff.deltas()
+        .flat_map(|delta| vec![delta.old_file().path(), delta.new_file().path()])
+        .filter_map(|path| path.and_then(|p| p.to_str()).map(|p| p.to_string()))
+        .collect();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// the sub-projects whose paths the commit touches
+pub fn touched_by(scopes: &[Scope], paths: &[String]) -> Vec<i32> {
+    scopes
+        .iter()
+        .filter(|scope| paths.iter().any(|path| scope.matches(path)))
+        .map(|scope| scope.project_id)
+        .collect()
+}
+
+/// records which sub-projects a commit belongs to
+pub fn record(conn: &PgConnection, sha: &str, project_ids: &[i32]) -> QueryResult<usize> {
+    use schema::commit_scopes;
+
+    let new_scopes: Vec<_> = project_ids
+        .iter()
+        .map(|&project_id| NewCommitScope {
+            commit_sha: sha,
+            project_id: project_id,
+        })
+        .collect();
+
+    diesel::insert_into(commit_scopes::table)
+        .values(&new_scopes)
+        .on_conflict_do_nothing()
+        .execute(conn)
+}
+
+/// works out which of the parent's commits belong to a sub-project, using the
+/// parent's local clone
+///
+/// Imports keep sub-projects up to date as commits come in, so this is only
+/// needed when a sub-project is created or its paths change.
+pub fn apply(log: &Logger, conn: &PgConnection, project: &Project) {
+    use schema::{commit_scopes, commits, releases};
+
+    let parent = parent_of(conn, project).expect("not a sub-project");
+    let repo = match parent.repo_path.as_ref().map(Repository::open) {
+        Some(Ok(repo)) => repo,
+        _ => {
+            warn!(
+                log,
+                "{} has no local clone, so {} can't be worked out", parent.name, project.name
+            );
+            return;
+        }
+    };
+
+    let globs: Vec<String> = globs_of(conn, project).into_iter().map(|g| g.glob).collect();
+    let globs: Vec<&str> = globs.iter().map(|g| &g[..]).collect();
+    let scope = Scope::new(project.id, &globs);
+
+    let shas: Vec<String> = commits::table
+        .inner_join(releases::table)
+        .filter(releases::project_id.eq(parent.id))
+        .select(commits::sha)
+        .load(conn)
+        .expect("Error loading commits");
+
+    // commits of other repositories aren't in the clone, and are left out
+    let scoped: Vec<String> = shas.into_iter()
+        .filter(|sha| {
+            Oid::from_str(sha)
+                .and_then(|id| repo.find_commit(id))
+                .map(|commit| {
+                    touched_paths(&repo, &commit)
+                        .iter()
+                        .any(|path| scope.matches(path))
+                })
+                .unwrap_or(false)
+        })
+        .collect();
+
+    info!(log, "{} commits of {} touch {}", scoped.len(), parent.name, project.name);
+
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::delete(commit_scopes::table.filter(commit_scopes::project_id.eq(project.id)))
+            .execute(conn)?;
+
+        // stay under the limit on bind parameters
+        for chunk in scoped.chunks(10_000) {
+            let new_scopes: Vec<_> = chunk
+                .iter()
+                .map(|sha| NewCommitScope {
+                    commit_sha: sha,
+                    project_id: project.id,
+                })
+                .collect();
+            diesel::insert_into(commit_scopes::table)
+                .values(&new_scopes)
+                .execute(conn)?;
+        }
+
+        Ok(())
+    }).expect("Error saving sub-project commits");
+}
+
+/// how many of the sub-project's commits each person made, counting
+/// co-authored commits like the all-time scores do
+pub fn scores(conn: &PgConnection, project: &Project) -> Vec<(Shown, i64)> {
+    People::load(conn).tally(contributions(conn, project).into_iter().map(|id| (id, 1)))
+}
+
+/// how many anonymous people contributed to the sub-project
+pub fn anonymous(conn: &PgConnection, project: &Project) -> usize {
+    People::load(conn).anonymous(contributions(conn, project))
+}
+
+/// the author of each of the sub-project's commits, and each co-author
+fn contributions(conn: &PgConnection, project: &Project) -> Vec<i32> {
+    use schema::{co_authors, commit_scopes, commits};
+    use diesel::expression::dsl::any;
+
+    let scoped = || {
+        commit_scopes::table
+            .filter(commit_scopes::project_id.eq(project.id))
+            .select(commit_scopes::commit_sha)
+    };
+
+    let authored: Vec<i32> = commits::table
+        .filter(commits::sha.eq(any(scoped())))
+        .filter(commits::backport_of.is_null())
+        .filter(commits::excluded.eq(false))
+        .select(commits::author_id)
+        .load(conn)
+        .expect("Error loading sub-project commits");
+
+    let co_authored: Vec<i32> = co_authors::table
+        .inner_join(commits::table)
+        .filter(commits::sha.eq(any(scoped())))
+        .filter(commits::backport_of.is_null())
+        .filter(commits::excluded.eq(false))
+        .select(co_authors::author_id)
+        .load(conn)
+        .expect("Error loading sub-project co-authors");
+
+    authored.into_iter().chain(co_authored).collect()
+}
+
+#[test]
+fn test_scopes() {
+    let rustdoc = Scope::new(1, &["src/librustdoc", "src/test/rustdoc*/**/*.rs"]);
+    let std = Scope::new(2, &["src/lib*/"]);
+
+    assert!(rustdoc.matches("src/librustdoc/html/render.rs"));
+    assert!(rustdoc.matches("src/librustdoc"));
+    assert!(!rustdoc.matches("src/librustdocs/lib.rs"));
+    assert!(rustdoc.matches("src/test/rustdoc/issue-41783.rs"));
+    assert!(rustdoc.matches("src/test/rustdoc-js/basic.rs"));
+    assert!(rustdoc.matches("src/test/rustdoc/auxiliary/mod.rs"));
+    assert!(!rustdoc.matches("src/test/rustdoc/issue-41783.html"));
+    assert!(!rustdoc.matches("src/test/rustdoc/issue-41783.rs/index.html"));
+
+    assert!(std.matches("src/libstd/io/mod.rs"));
+    assert!(std.matches("src/librustdoc/lib.rs"));
+    assert!(!std.matches("src/tools/cargo"));
+
+    let paths = vec!["src/libcore/iter.rs".to_string(), "README.md".to_string()];
+    assert_eq!(touched_by(&[rustdoc, std], &paths), vec![2]);
+}
@@ -0,0 +1,330 @@
+use models::{IgnoredPath, NewIgnoredPath, Project};
+
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use git2::{Commit, Diff, Oid, Patch, Repository};
+
+use regex::Regex;
+
+use people::{Key, People, Shown};
+use scopes;
+
+use slog::Logger;
+
+use std::collections::{HashMap, HashSet};
+
+/// what the all-time leaderboard ranks people by
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Metric {
+    /// commits authored or co-authored
+    Commits,
+    /// lines added and removed, outside of ignored paths
+    Lines,
+    /// releases with at least one of their commits
+    Releases,
+}
+
+impl Metric {
+    /// the heading of the column with the scores
+    pub fn title(&self) -> &'static str {
+        match *self {
+            Metric::Commits => "Commits",
+            Metric::Lines => "Lines changed",
+            Metric::Releases => "Releases",
+        }
+    }
+
+    /// what the scores are stored under in the page data
+    pub fn key(&self) -> &'static str {
+        match *self {
+            Metric::Commits => "commits",
+            Metric::Lines => "lines",
+            Metric::Releases => "releases",
+        }
+    }
+}
+
+/// the size of a commit's change
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct LineStats {
+    pub insertions: i32,
+    pub deletions: i32,
+    pub files_changed: i32,
+}
+
+/// the diff of a commit against its first parent, or against nothing for the
+/// first commit
+pub fn first_parent_diff<'a>(repo: &'a Repository, commit: &Commit) -> Option<Diff<'a>> {
+    let tree = commit.tree().ok();
+    let parent_tree = commit.parent(0).ok().and_then(|parent| parent.tree().ok());
+
+    repo.diff_tree_to_tree(parent_tree.as_ref(), tree.as_ref(), None)
+        .ok()
+}
+
+/// counts the lines a commit changes, leaving out files under `ignored`
+///
+/// Merge commits don't have a change of their own, so like `patch_id` they
+/// get `None`.
+pub fn of_commit(repo: &Repository, commit: &Commit, ignored: &[Regex]) -> Option<LineStats> {
+    if commit.parent_count() > 1 {
+        return None;
+    }
+
+    let diff = match first_parent_diff(repo, commit) {
+        Some(diff) => diff,
+        None => return None,
+    };
+
+    let mut stats = LineStats::default();
+    for (idx, delta) in diff.deltas().enumerate() {
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .and_then(|p| p.to_str())
+            .unwrap_or("");
+        if ignored.iter().any(|re| re.is_match(path)) {
+            continue;
+        }
+
+        stats.files_changed += 1;
+
+        // binary files don't have lines
+        if let Ok(Some(patch)) = Patch::from_diff(&diff, idx) {
+            if let Ok((_, insertions, deletions)) = patch.line_stats() {
+                stats.insertions += insertions as i32;
+                stats.deletions += deletions as i32;
+            }
+        }
+    }
+
+    Some(stats)
+}
+
+/// the compiled ignored paths of a project
+pub fn ignored_for_project(conn: &PgConnection, ignored_project_id: i32) -> Vec<Regex> {
+    use schema::ignored_paths::dsl::*;
+
+    ignored_paths
+        .filter(project_id.eq(ignored_project_id))
+        .load::<IgnoredPath>(conn)
+        .expect("Error loading ignored paths")
+        .iter()
+        .map(|path| scopes::compile(&path.glob))
+        .collect()
+}
+
+pub fn ignored_paths_of(conn: &PgConnection, project: &Project) -> Vec<IgnoredPath> {
+    IgnoredPath::belonging_to(project)
+        .load(conn)
+        .expect("Error loading ignored paths")
+}
+
+pub fn add_ignored_path(conn: &PgConnection, project: &Project, glob: &str) -> IgnoredPath {
+    use schema::ignored_paths;
+
+    // fail now rather than on the next import
+    scopes::compile(glob);
+
+    diesel::insert_into(ignored_paths::table)
+        .values(&NewIgnoredPath {
+            project_id: project.id,
+            glob: glob,
+        })
+        .get_result(conn)
+        .expect("Error saving ignored path")
+}
+
+/// removes an ignored path, returning how many there were
+pub fn remove_ignored_path(conn: &PgConnection, project: &Project, path_glob: &str) -> usize {
+    use schema::ignored_paths::dsl::*;
+
+    diesel::delete(
+        ignored_paths
+            .filter(project_id.eq(project.id))
+            .filter(glob.eq(path_glob)),
+    ).execute(conn)
+        .expect("Error removing ignored path")
+}
+
+/// counts the lines of all the commits of a project again, from its local
+/// clone
+///
+/// Imports count the lines as commits come in, so this is only needed when
+/// the ignored paths change, or for commits imported before we counted them.
+pub fn apply(log: &Logger, conn: &PgConnection, project: &Project) {
+    use schema::{commits, releases};
+
+    let repo = match project.repo_path.as_ref().map(Repository::open) {
+        Some(Ok(repo)) => repo,
+        _ => {
+            warn!(log, "{} has no local clone to count lines in", project.name);
+            return;
+        }
+    };
+
+    let ignored = ignored_for_project(conn, project.id);
+
+    let shas: Vec<String> = commits::table
+        .inner_join(releases::table)
+        .filter(releases::project_id.eq(project.id))
+        .select(commits::sha)
+        .load(conn)
+        .expect("Error loading commits");
+
+    info!(log, "Counting the lines of {} commits of {}", shas.len(), project.name);
+
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        for sha in &shas {
+            // commits of other repositories aren't in the clone
+            let stats = match Oid::from_str(sha).and_then(|id| repo.find_commit(id)) {
+                Ok(commit) => of_commit(&repo, &commit, &ignored),
+                Err(_) => continue,
+            };
+
+            diesel::update(commits::table.find(sha))
+                .set((
+                    commits::insertions.eq(stats.map(|s| s.insertions)),
+                    commits::deletions.eq(stats.map(|s| s.deletions)),
+                    commits::files_changed.eq(stats.map(|s| s.files_changed)),
+                ))
+                .execute(conn)?;
+        }
+
+        Ok(())
+    }).expect("Error saving line counts");
+}
+
+/// how many lines each person changed, counting co-authored commits like the
+/// commit scores do
+pub fn line_counts(conn: &PgConnection) -> Vec<(Shown, i64)> {
+    use schema::{co_authors, commits};
+    use diesel::expression::dsl::sql;
+    use diesel::types::BigInt;
+
+    let lines = "COALESCE(SUM(commits.insertions + commits.deletions), 0)::BIGINT";
+
+    let authored: Vec<(i32, i64)> = commits::table
+        .filter(commits::backport_of.is_null())
+        .filter(commits::excluded.eq(false))
+        .select((commits::author_id, sql::<BigInt>(lines)))
+        .group_by(commits::author_id)
+        .load(conn)
+        .expect("Error counting lines");
+
+    let co_authored: Vec<(i32, i64)> = co_authors::table
+        .inner_join(commits::table)
+        .filter(commits::backport_of.is_null())
+        .filter(commits::excluded.eq(false))
+        .select((co_authors::author_id, sql::<BigInt>(lines)))
+        .group_by(co_authors::author_id)
+        .load(conn)
+        .expect("Error counting co-authored lines");
+
+    People::load(conn).tally(authored.into_iter().chain(co_authored))
+}
+
+/// how many releases each person contributed to, leaving out the branch and
+/// the channels
+pub fn release_counts(conn: &PgConnection) -> Vec<(Shown, i64)> {
+    use schema::{co_authors, commits, releases};
+
+    let authored: Vec<(i32, i32)> = commits::table
+        .inner_join(releases::table)
+        .filter(commits::excluded.eq(false))
+        .filter(releases::pseudo.eq(false))
+        .select((commits::author_id, commits::release_id))
+        .distinct()
+        .load(conn)
+        .expect("Error loading releases of authors");
+
+    let co_authored: Vec<(i32, i32)> = co_authors::table
+        .inner_join(commits::table.inner_join(releases::table))
+        .filter(commits::excluded.eq(false))
+        .filter(releases::pseudo.eq(false))
+        .select((co_authors::author_id, commits::release_id))
+        .distinct()
+        .load(conn)
+        .expect("Error loading releases of co-authors");
+
+    // someone who contributed to a release under several identities, or both
+    // wrote and co-wrote its commits, counts it once
+    let people = People::load(conn);
+    let contributions: HashSet<(Key, i32)> = authored
+        .into_iter()
+        .chain(co_authored)
+        .filter_map(|(author, release)| people.of(author).map(|key| (key, release)))
+        .collect();
+
+    let mut counts: HashMap<Key, i64> = HashMap::new();
+    for (key, _) in contributions {
+        *counts.entry(key).or_insert(0) += 1;
+    }
+
+    counts
+        .into_iter()
+        .map(|(key, count)| (people.shown(key), count))
+        .collect()
+}
+
+#[test]
+fn test_line_stats() {
+    use commits::TempRepo;
+    use git2::Signature;
+
+    let repo = TempRepo::new("line-stats");
+    let sig = Signature::now("Ana Lopez", "ana@example.com").unwrap();
+
+    let commit_files = |files: &[(&str, &str)], parents: &[&Commit]| {
+        let mut vendor = repo.treebuilder(None).unwrap();
+        let mut root = repo.treebuilder(None).unwrap();
+        for &(name, content) in files {
+            let blob = repo.blob(content.as_bytes()).unwrap();
+            if name.starts_with("vendor/") {
+                vendor.insert(&name[7..], blob, 0o100644).unwrap();
+            } else {
+                root.insert(name, blob, 0o100644).unwrap();
+            }
+        }
+        root.insert("vendor", vendor.write().unwrap(), 0o040000).unwrap();
+        let tree = repo.find_tree(root.write().unwrap()).unwrap();
+        let id = repo.commit(None, &sig, &sig, "commit", &tree, parents).unwrap();
+        repo.find_commit(id).unwrap()
+    };
+
+    let first = commit_files(&[("lib.rs", "a\nb\nc\n"), ("vendor/dep.rs", "x\n")], &[]);
+    let second = commit_files(
+        &[("lib.rs", "a\nB\nc\nd\n"), ("vendor/dep.rs", "y\nz\n")],
+        &[&first],
+    );
+    let merge = commit_files(&[("lib.rs", "a\n")], &[&second, &first]);
+
+    assert_eq!(
+        of_commit(&repo, &first, &[]),
+        Some(LineStats {
+            insertions: 4,
+            deletions: 0,
+            files_changed: 2,
+        })
+    );
+    assert_eq!(
+        of_commit(&repo, &second, &[]),
+        Some(LineStats {
+            insertions: 4,
+            deletions: 2,
+            files_changed: 2,
+        })
+    );
+    assert_eq!(
+        of_commit(&repo, &second, &[scopes::compile("vendor")]),
+        Some(LineStats {
+            insertions: 2,
+            deletions: 1,
+            files_changed: 1,
+        })
+    );
+    assert_eq!(of_commit(&repo, &merge, &[]), None);
+}
@@ -0,0 +1,867 @@
+use models::{Author, Commit, NameOverride, NewAuthor, NewCommit, Person, Project, Release};
+
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use authors::{self, AuthorStore};
+use mailmap::Mailmap;
+use policies::Policy;
+use reviews::Reviewer;
+use scopes::Scope;
+
+use regex::Regex;
+
+use std::collections::{HashMap, HashSet};
+
+/// what importing commits, from the GitHub API or a local clone, and listing
+/// releases and their contributors need from the database
+///
+/// `Postgres` is the real thing. `Memory` keeps everything in memory, so the
+/// code written against this can be tested without a database. The rest still
+/// works on a `PgConnection` directly: walking a local clone and keeping
+/// channels up to date (`sync::from_repo` and `channels::sync`, which hand the
+/// commits they find to `releases::assign_commits`), and the line, release and
+/// sub-project scores.
+pub trait Storage {
+    /// a project, by its case-insensitive name
+    fn project(&self, name: &str) -> Option<Project>;
+
+    fn release(&self, project_id: i32, version: &str) -> Option<Release>;
+
+    /// all the releases of a project, visible or not
+    fn releases(&self, project_id: i32) -> Vec<Release>;
+
+    /// the commits of a channel release, which belong to other releases, or
+    /// `None` if the release isn't a channel
+    fn channel_commits(&self, release: &Release) -> Option<Vec<String>>;
+
+    /// which of the project's commits go uncredited
+    fn policy(&self, project_id: i32) -> Policy;
+
+    /// the paths of the project's sub-projects
+    fn scopes(&self, parent_id: i32) -> Vec<Scope>;
+
+    /// the paths left out of the project's line counts
+    fn ignored_paths(&self, project_id: i32) -> Vec<Regex>;
+
+    /// remembers the ETag of the project's commits, to skip the next sync if
+    /// nothing changed
+    fn set_github_etag(&mut self, project: &Project, etag: Option<&str>);
+
+    /// the commits of `shas` that one of the project's releases has
+    fn known_commits(&self, project_id: i32, shas: &[&str]) -> HashSet<String>;
+
+    /// the identity the `.mailmap` maps a name and email to
+    fn map_identity(&mut self, name: &str, email: &str) -> (String, String);
+
+    /// finds or creates the author with the identity
+    fn author(&mut self, name: &str, email: &str) -> Author;
+
+    /// finds or creates the authors of many identities at once
+    fn authors_of(&mut self, identities: &[(String, String)]) -> Vec<Author> {
+        identities
+            .iter()
+            .map(|&(ref name, ref email)| self.author(name, email))
+            .collect()
+    }
+
+    fn authors(&self) -> Vec<Author>;
+
+    fn people(&self) -> Vec<Person>;
+
+    fn name_overrides(&self) -> Vec<NameOverride>;
+
+    fn add_commit(&mut self, commit: &NewCommit);
+
+    /// adds commits, moving the ones that already exist to the release of
+    /// their new version
+    fn save_commits(&mut self, commits: &[NewCommit]);
+
+    fn add_co_authors(&mut self, sha: &str, co_authors: &[Author]);
+
+    fn add_reviews(&mut self, sha: &str, reviewers: &[Reviewer]);
+
+    /// records which sub-projects a commit belongs to
+    fn add_commit_scopes(&mut self, sha: &str, project_ids: &[i32]);
+
+    /// the commits of the project's releases that carry one of the patches
+    fn commits_with_patch_ids(&self, project_id: i32, patch_ids: &[&str]) -> Vec<Commit>;
+
+    /// marks a commit as a backport of `original`, crediting it to the
+    /// original's author
+    fn mark_backport(&mut self, sha: &str, original: &Commit);
+
+    /// runs `f` so that either all or none of what it saves is kept
+    fn atomically<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Self),
+        Self: Sized;
+
+    /// the authors and co-authors of the credited commits of a release, or of
+    /// the project's part of it for sub-projects
+    fn contributor_ids(&self, release: &Release, project: &Project) -> Vec<i32>;
+
+    /// how many credited commits each author wrote or co-wrote, leaving out
+    /// backports
+    fn commit_counts(&self) -> Vec<(i32, i64)>;
+}
+
+pub struct Postgres<'a: 's, 's> {
+    conn: &'a PgConnection,
+    authors: Authors<'a, 's>,
+}
+
+enum Authors<'a: 's, 's> {
+    // only made once authors are looked up, as that loads the opt-outs
+    Unloaded,
+    Owned(AuthorStore<'a>),
+    Borrowed(&'s mut AuthorStore<'a>),
+}
+
+impl<'a, 's> Postgres<'a, 's> {
+    pub fn new(conn: &'a PgConnection) -> Postgres<'a, 's> {
+        Postgres {
+            conn: conn,
+            authors: Authors::Unloaded,
+        }
+    }
+
+    /// looks authors up through `authors`, and its `.mailmap`
+    pub fn with_authors(conn: &'a PgConnection, authors: AuthorStore<'a>) -> Postgres<'a, 's> {
+        Postgres {
+            conn: conn,
+            authors: Authors::Owned(authors),
+        }
+    }
+
+    /// like `with_authors`, for callers that keep using the store afterwards
+    pub fn borrowing_authors(authors: &'s mut AuthorStore<'a>) -> Postgres<'a, 's> {
+        Postgres {
+            conn: authors.connection(),
+            authors: Authors::Borrowed(authors),
+        }
+    }
+
+    fn author_store(&mut self) -> &mut AuthorStore<'a> {
+        if let Authors::Unloaded = self.authors {
+            self.authors = Authors::Owned(AuthorStore::new(self.conn, Mailmap::new("")));
+        }
+        match self.authors {
+            Authors::Owned(ref mut authors) => authors,
+            Authors::Borrowed(ref mut authors) => authors,
+            Authors::Unloaded => unreachable!(),
+        }
+    }
+}
+
+impl<'a, 's> Storage for Postgres<'a, 's> {
+    fn project(&self, name: &str) -> Option<Project> {
+        ::releases::find_project(self.conn, name)
+    }
+
+    fn release(&self, release_project_id: i32, release_version: &str) -> Option<Release> {
+        use schema::releases::dsl::*;
+
+        releases
+            .filter(project_id.eq(release_project_id))
+            .filter(version.eq(release_version))
+            .first(self.conn)
+            .ok()
+    }
+
+    fn releases(&self, release_project_id: i32) -> Vec<Release> {
+        use schema::releases::dsl::*;
+
+        releases
+            .filter(project_id.eq(release_project_id))
+            .load(self.conn)
+            .expect("Error loading releases")
+    }
+
+    fn channel_commits(&self, release: &Release) -> Option<Vec<String>> {
+        ::channels::commits_of(self.conn, release)
+    }
+
+    fn policy(&self, project_id: i32) -> Policy {
+        Policy::for_project(self.conn, project_id)
+    }
+
+    fn scopes(&self, parent_id: i32) -> Vec<Scope> {
+        ::scopes::for_project(self.conn, parent_id)
+    }
+
+    fn ignored_paths(&self, project_id: i32) -> Vec<Regex> {
+        ::stats::ignored_for_project(self.conn, project_id)
+    }
+
+    fn set_github_etag(&mut self, project: &Project, etag: Option<&str>) {
+        ::projects::set_github_etag(self.conn, project, etag);
+    }
+
+    fn known_commits(&self, project_id: i32, shas: &[&str]) -> HashSet<String> {
+        use schema::{commits, releases};
+        use diesel::expression::dsl::any;
+
+        commits::table
+            .inner_join(releases::table)
+            .filter(releases::project_id.eq(project_id))
+            .filter(commits::sha.eq(any(shas)))
+            .select(commits::sha)
+            .load::<String>(self.conn)
+            .expect("Error loading commits")
+            .into_iter()
+            .collect()
+    }
+
+    fn map_identity(&mut self, name: &str, email: &str) -> (String, String) {
+        self.author_store().get_mailmap().map(name, email)
+    }
+
+    fn author(&mut self, name: &str, email: &str) -> Author {
+        self.author_store().get(name, email)
+    }
+
+    fn authors_of(&mut self, identities: &[(String, String)]) -> Vec<Author> {
+        let authors = self.author_store();
+        let new_authors = identities
+            .iter()
+            .map(|&(ref name, ref email)| NewAuthor {
+                email: email.as_str(),
+                name: name.as_str(),
+                github_login: authors::github_login(email),
+            })
+            .collect();
+        authors.find_or_create_all(new_authors);
+
+        // looked up by identity rather than by the name and email of the authors,
+        // which differ for identities that were merged into another author
+        identities
+            .iter()
+            .map(|&(ref name, ref email)| authors.get(name, email))
+            .collect()
+    }
+
+    fn authors(&self) -> Vec<Author> {
+        use schema::authors;
+
+        authors::table
+            .load(self.conn)
+            .expect("Error loading authors")
+    }
+
+    fn people(&self) -> Vec<Person> {
+        use schema::people;
+
+        people::table
+            .load(self.conn)
+            .expect("Error loading people")
+    }
+
+    fn name_overrides(&self) -> Vec<NameOverride> {
+        ::people::overrides(self.conn)
+    }
+
+    fn add_commit(&mut self, commit: &NewCommit) {
+        use schema::commits;
+
+        diesel::insert_into(commits::table)
+            .values(commit)
+            .execute(self.conn)
+            .expect("Error saving new commit");
+    }
+
+    fn save_commits(&mut self, new_commits: &[NewCommit]) {
+        use schema::commits;
+        use diesel::pg::upsert::*;
+
+        let saved = diesel::insert_into(commits::table)
+            .values(new_commits)
+            .on_conflict(commits::sha)
+            .do_update()
+            .set((
+                commits::release_id.eq(excluded(commits::release_id)),
+                commits::patch_id.eq(excluded(commits::patch_id)),
+                commits::is_merge.eq(excluded(commits::is_merge)),
+                commits::excluded.eq(excluded(commits::excluded)),
+                commits::insertions.eq(excluded(commits::insertions)),
+                commits::deletions.eq(excluded(commits::deletions)),
+                commits::files_changed.eq(excluded(commits::files_changed)),
+            ))
+            .execute(self.conn)
+            .expect("Error saving commits");
+
+        if saved != new_commits.len() {
+            panic!(
+                "Expected to create or update {} commits, but only {} were",
+                new_commits.len(),
+                saved
+            );
+        }
+    }
+
+    fn add_co_authors(&mut self, sha: &str, co_authors: &[Author]) {
+        ::commits::add_co_authors(self.conn, sha, co_authors);
+    }
+
+    fn add_reviews(&mut self, sha: &str, reviewers: &[Reviewer]) {
+        let conn = self.conn;
+        ::reviews::record(conn, self.author_store(), sha, reviewers);
+    }
+
+    fn add_commit_scopes(&mut self, sha: &str, project_ids: &[i32]) {
+        ::scopes::record(self.conn, sha, project_ids).expect("Error saving commit scopes");
+    }
+
+    fn commits_with_patch_ids(&self, project_id: i32, patch_ids: &[&str]) -> Vec<Commit> {
+        use schema::{commits, releases};
+        use diesel::expression::dsl::any;
+
+        let project_releases = releases::table
+            .filter(releases::project_id.eq(project_id))
+            .select(releases::id);
+
+        commits::table
+            .filter(commits::patch_id.eq(any(patch_ids)))
+            .filter(commits::release_id.eq(any(project_releases)))
+            .load(self.conn)
+            .expect("Error loading commits")
+    }
+
+    fn mark_backport(&mut self, copy_sha: &str, original: &Commit) {
+        use schema::commits::dsl::*;
+
+        diesel::update(commits.find(copy_sha))
+            .set((
+                backport_of.eq(original.sha.as_str()),
+                author_id.eq(original.author_id),
+            ))
+            .execute(self.conn)
+            .expect("Error linking backport");
+    }
+
+    fn atomically<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        let conn = self.conn;
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            f(self);
+            Ok(())
+        }).expect("Error committing transaction");
+    }
+
+    fn contributor_ids(&self, release: &Release, project: &Project) -> Vec<i32> {
+        use schema::{commit_scopes, commits};
+        use diesel::expression::dsl::any;
+
+        let mut query = commits::table
+            .filter(commits::excluded.eq(false))
+            .select(commits::author_id)
+            .distinct()
+            .into_boxed();
+
+        // channels hold commits that belong to other releases
+        query = match self.channel_commits(release) {
+            Some(shas) => query.filter(commits::sha.eq(any(shas))),
+            None => query.filter(commits::release_id.eq(release.id)),
+        };
+
+        // sub-projects only thank the people who touched their paths
+        if project.parent_id.is_some() {
+            let scoped = commit_scopes::table
+                .filter(commit_scopes::project_id.eq(project.id))
+                .select(commit_scopes::commit_sha);
+            query = query.filter(commits::sha.eq(any(scoped)));
+        }
+
+        let mut ids: Vec<i32> = query.load(self.conn).expect("Error loading contributors");
+        ids.extend(::commits::co_author_ids(self.conn, release, project));
+        ids
+    }
+
+    fn commit_counts(&self) -> Vec<(i32, i64)> {
+        use schema::commits::dsl::*;
+        use diesel::expression::dsl::sql;
+        use diesel::types::BigInt;
+
+        let mut counts: Vec<(i32, i64)> = commits
+            .filter(backport_of.is_null())
+            .filter(excluded.eq(false))
+            .select((author_id, sql::<BigInt>("COUNT(author_id)")))
+            .group_by(author_id)
+            .load(self.conn)
+            .expect("Error counting commits");
+
+        // co-authored commits count as much as authored ones
+        counts.extend(::commits::co_author_counts(self.conn));
+        counts
+    }
+}
+
+/// a storage that only lives as long as it's used, for tests
+///
+/// Authors are normalized like in the database and mapped through the
+/// `.mailmap` given to `set_mailmap`, but there are no opt-outs, aliases, bots
+/// or ignored paths.
+#[derive(Default)]
+pub struct Memory {
+    projects: Vec<Project>,
+    releases: Vec<Release>,
+    channels: Vec<(i32, Vec<String>)>,
+    mailmap: Mailmap,
+    authors: Vec<Author>,
+    people: Vec<Person>,
+    overrides: Vec<NameOverride>,
+    commits: Vec<Commit>,
+    co_authors: Vec<(String, i32)>,
+    reviews: Vec<(String, Reviewer)>,
+    scope_paths: Vec<(i32, String)>,
+    scopes: Vec<(String, i32)>,
+}
+
+impl Memory {
+    pub fn new() -> Memory {
+        Memory::default()
+    }
+
+    pub fn add_project(&mut self, name: &str, parent_id: Option<i32>) -> Project {
+        let project = Project {
+            id: self.projects.len() as i32 + 1,
+            name: name.to_string(),
+            url_path: name.to_lowercase(),
+            github_name: String::new(),
+            version_scheme: "semver".to_string(),
+            repo_path: None,
+            last_synced: None,
+            github_etag: None,
+            follow_submodules: false,
+            skip_merges: false,
+            parent_id: parent_id,
+            branch: "master".to_string(),
+            branch_title: None,
+        };
+        self.projects.push(project.clone());
+        project
+    }
+
+    pub fn add_release(&mut self, project_id: i32, version: &str) -> Release {
+        let is_branch = self.projects
+            .iter()
+            .any(|p| p.id == project_id && p.branch == version);
+        self.push_release(project_id, version, is_branch)
+    }
+
+    /// adds a channel release that's made of commits of other releases
+    pub fn add_channel(&mut self, project_id: i32, name: &str, shas: &[&str]) -> Release {
+        let release = self.push_release(project_id, name, true);
+        let shas = shas.iter().map(|sha| sha.to_string()).collect();
+        self.channels.push((release.id, shas));
+        release
+    }
+
+    fn push_release(&mut self, project_id: i32, version: &str, pseudo: bool) -> Release {
+        let release = Release {
+            id: self.releases.len() as i32 + 1,
+            version: version.to_string(),
+            project_id: project_id,
+            visible: true,
+            link: String::new(),
+            pseudo: pseudo,
+            released_at: None,
+        };
+        self.releases.push(release.clone());
+        release
+    }
+
+    pub fn set_mailmap(&mut self, data: &str) {
+        self.mailmap = Mailmap::new(data);
+    }
+
+    pub fn add_person(&mut self, name: &str) -> Person {
+        let person = Person {
+            id: self.people.len() as i32 + 1,
+            name: name.to_string(),
+            github_login: None,
+        };
+        self.people.push(person.clone());
+        person
+    }
+
+    pub fn attach(&mut self, person: &Person, author: &Author) {
+        for known in self.authors.iter_mut().filter(|a| a.id == author.id) {
+            known.person_id = Some(person.id);
+        }
+    }
+
+    pub fn add_name_override(&mut self, author: &Author, display_name: &str) {
+        self.overrides.push(NameOverride {
+            author_id: author.id,
+            display_name: display_name.to_string(),
+        });
+    }
+
+    /// adds a path glob to a sub-project, for the commits imported after it
+    pub fn add_scope_path(&mut self, project: &Project, glob: &str) {
+        self.scope_paths.push((project.id, glob.to_string()));
+    }
+
+    /// marks a commit as touching the paths of a sub-project
+    pub fn add_scope(&mut self, sha: &str, project_id: i32) {
+        self.scopes.push((sha.to_string(), project_id));
+    }
+
+    pub fn reviews(&self) -> &[(String, Reviewer)] {
+        &self.reviews
+    }
+
+    fn credited(&self) -> Vec<&Commit> {
+        self.commits.iter().filter(|commit| !commit.excluded).collect()
+    }
+
+    fn co_authors_of(&self, sha: &str) -> Vec<i32> {
+        self.co_authors
+            .iter()
+            .filter(|&&(ref commit_sha, _)| commit_sha == sha)
+            .map(|&(_, author_id)| author_id)
+            .collect()
+    }
+}
+
+impl Storage for Memory {
+    fn project(&self, name: &str) -> Option<Project> {
+        self.projects
+            .iter()
+            .find(|project| project.name.to_lowercase() == name.to_lowercase())
+            .cloned()
+    }
+
+    fn release(&self, project_id: i32, version: &str) -> Option<Release> {
+        self.releases
+            .iter()
+            .find(|release| release.project_id == project_id && release.version == version)
+            .cloned()
+    }
+
+    fn releases(&self, project_id: i32) -> Vec<Release> {
+        self.releases
+            .iter()
+            .filter(|release| release.project_id == project_id)
+            .cloned()
+            .collect()
+    }
+
+    fn channel_commits(&self, release: &Release) -> Option<Vec<String>> {
+        self.channels
+            .iter()
+            .find(|&&(release_id, _)| release_id == release.id)
+            .map(|&(_, ref shas)| shas.clone())
+    }
+
+    fn policy(&self, project_id: i32) -> Policy {
+        let skip_merges = self.projects
+            .iter()
+            .any(|project| project.id == project_id && project.skip_merges);
+        Policy::new(skip_merges, &[])
+    }
+
+    fn scopes(&self, parent_id: i32) -> Vec<Scope> {
+        self.projects
+            .iter()
+            .filter(|project| project.parent_id == Some(parent_id))
+            .map(|project| {
+                let globs: Vec<&str> = self.scope_paths
+                    .iter()
+                    .filter(|&&(project_id, _)| project_id == project.id)
+                    .map(|&(_, ref glob)| &glob[..])
+                    .collect();
+                Scope::new(project.id, &globs)
+            })
+            .collect()
+    }
+
+    fn ignored_paths(&self, _project_id: i32) -> Vec<Regex> {
+        Vec::new()
+    }
+
+    fn set_github_etag(&mut self, project: &Project, etag: Option<&str>) {
+        for known in self.projects.iter_mut().filter(|known| known.id == project.id) {
+            known.github_etag = etag.map(|etag| etag.to_string());
+        }
+    }
+
+    fn known_commits(&self, project_id: i32, shas: &[&str]) -> HashSet<String> {
+        self.commits
+            .iter()
+            .filter(|commit| {
+                shas.contains(&&commit.sha[..]) && self.releases
+                    .iter()
+                    .any(|release| release.id == commit.release_id && release.project_id == project_id)
+            })
+            .map(|commit| commit.sha.clone())
+            .collect()
+    }
+
+    fn map_identity(&mut self, name: &str, email: &str) -> (String, String) {
+        self.mailmap.map(name, email)
+    }
+
+    fn author(&mut self, name: &str, email: &str) -> Author {
+        let (name, email) = authors::normalize(name, email);
+
+        if let Some(author) = self.authors
+            .iter()
+            .find(|author| author.name == name && author.email == email)
+        {
+            return author.clone();
+        }
+
+        let author = Author {
+            id: self.authors.len() as i32 + 1,
+            github_login: authors::github_login(&email).map(|login| login.to_string()),
+            name: name,
+            email: email,
+            visible: true,
+            person_id: None,
+            anonymous: false,
+        };
+        self.authors.push(author.clone());
+        author
+    }
+
+    fn authors(&self) -> Vec<Author> {
+        self.authors.clone()
+    }
+
+    fn people(&self) -> Vec<Person> {
+        self.people.clone()
+    }
+
+    fn name_overrides(&self) -> Vec<NameOverride> {
+        self.overrides.clone()
+    }
+
+    fn add_commit(&mut self, commit: &NewCommit) {
+        if self.commits.iter().any(|known| known.sha == commit.sha) {
+            panic!("Error saving new commit: {} already exists", commit.sha);
+        }
+
+        self.commits.push(Commit {
+            sha: commit.sha.to_string(),
+            release_id: commit.release_id,
+            author_id: commit.author_id,
+            patch_id: commit.patch_id.map(|id| id.to_string()),
+            backport_of: None,
+            repository_id: None,
+            is_merge: commit.is_merge,
+            excluded: commit.excluded,
+            insertions: commit.insertions,
+            deletions: commit.deletions,
+            files_changed: commit.files_changed,
+        });
+    }
+
+    fn save_commits(&mut self, commits: &[NewCommit]) {
+        for commit in commits {
+            match self.commits.iter().position(|known| known.sha == commit.sha) {
+                Some(i) => {
+                    let known = &mut self.commits[i];
+                    known.release_id = commit.release_id;
+                    known.patch_id = commit.patch_id.map(|id| id.to_string());
+                    known.is_merge = commit.is_merge;
+                    known.excluded = commit.excluded;
+                    known.insertions = commit.insertions;
+                    known.deletions = commit.deletions;
+                    known.files_changed = commit.files_changed;
+                }
+                None => self.add_commit(commit),
+            }
+        }
+    }
+
+    fn add_co_authors(&mut self, sha: &str, co_authors: &[Author]) {
+        for author in co_authors {
+            let row = (sha.to_string(), author.id);
+            if !self.co_authors.contains(&row) {
+                self.co_authors.push(row);
+            }
+        }
+    }
+
+    fn add_reviews(&mut self, sha: &str, reviewers: &[Reviewer]) {
+        for reviewer in reviewers {
+            self.reviews.push((sha.to_string(), reviewer.clone()));
+        }
+    }
+
+    fn add_commit_scopes(&mut self, sha: &str, project_ids: &[i32]) {
+        for &project_id in project_ids {
+            let row = (sha.to_string(), project_id);
+            if !self.scopes.contains(&row) {
+                self.scopes.push(row);
+            }
+        }
+    }
+
+    fn commits_with_patch_ids(&self, project_id: i32, patch_ids: &[&str]) -> Vec<Commit> {
+        self.commits
+            .iter()
+            .filter(|commit| match commit.patch_id {
+                Some(ref id) => patch_ids.contains(&&id[..]),
+                None => false,
+            })
+            .filter(|commit| {
+                self.releases.iter().any(|release| {
+                    release.id == commit.release_id && release.project_id == project_id
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn mark_backport(&mut self, sha: &str, original: &Commit) {
+        for copy in self.commits.iter_mut().filter(|commit| commit.sha == sha) {
+            copy.backport_of = Some(original.sha.clone());
+            copy.author_id = original.author_id;
+        }
+    }
+
+    fn atomically<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        f(self);
+    }
+
+    fn contributor_ids(&self, release: &Release, project: &Project) -> Vec<i32> {
+        // channels hold commits that belong to other releases
+        let channel = self.channel_commits(release);
+        let mut ids: Vec<i32> = self.credited()
+            .into_iter()
+            .filter(|commit| match channel {
+                Some(ref shas) => shas.contains(&commit.sha),
+                None => commit.release_id == release.id,
+            })
+            .filter(|commit| {
+                project.parent_id.is_none()
+                    || self.scopes.contains(&(commit.sha.clone(), project.id))
+            })
+            .flat_map(|commit| {
+                Some(commit.author_id)
+                    .into_iter()
+                    .chain(self.co_authors_of(&commit.sha))
+            })
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    fn commit_counts(&self) -> Vec<(i32, i64)> {
+        let mut counts: HashMap<i32, i64> = HashMap::new();
+        for commit in self.credited() {
+            if commit.backport_of.is_some() {
+                continue;
+            }
+            for author_id in Some(commit.author_id)
+                .into_iter()
+                .chain(self.co_authors_of(&commit.sha))
+            {
+                *counts.entry(author_id).or_insert(0) += 1;
+            }
+        }
+        counts.into_iter().collect()
+    }
+}
+
+#[test]
+fn test_memory_storage() {
+    use github::GitHubCommit;
+    use slog;
+
+    let log = slog::Logger::root(slog::Discard, o!());
+    let mut storage = Memory::new();
+
+    let rust = storage.add_project("Rust", None);
+    let rustdoc = storage.add_project("rustdoc", Some(rust.id));
+    let master = storage.add_release(rust.id, "master");
+
+    let commit = |sha: &str, name: &str, email: &str| GitHubCommit {
+        sha: sha.to_string(),
+        name: name.to_string(),
+        email: email.to_string(),
+        is_merge: false,
+        co_authors: Vec::new(),
+        reviewers: Vec::new(),
+    };
+
+    // newest first, like the API lists them
+    let commits = vec![
+        GitHubCommit {
+            is_merge: true,
+            reviewers: vec![Reviewer::Handle("alexcrichton".to_string())],
+            ..commit("c3", "bors", "bors@rust-lang.org")
+        },
+        GitHubCommit {
+            co_authors: vec![("Ana Lopez".to_string(), "ana@example.com".to_string())],
+            ..commit("c2", "Jo Smith", "jo@example.org")
+        },
+        commit("c1", "Jose\u{301}", "12345+jose@users.noreply.github.com"),
+        commit("c0", "Ana Lopez", "ana@example.com"),
+    ];
+
+    let policy = Policy::new(true, &[]);
+    ::github::save(&log, &mut storage, &master, &policy, &commits);
+
+    let known = storage.known_commits(rust.id, &["c1", "c9"]);
+    assert_eq!(known.into_iter().collect::<Vec<_>>(), vec!["c1".to_string()]);
+    assert_eq!(storage.reviews().len(), 1);
+
+    // bors' merge isn't credited, and the co-author counts
+    let mut scores: Vec<_> = ::commit_counts(&storage)
+        .into_iter()
+        .map(|(shown, score)| (shown.name, score))
+        .collect();
+    scores.sort();
+    assert_eq!(
+        scores,
+        vec![
+            ("Ana Lopez".to_string(), 2),
+            ("Jo Smith".to_string(), 1),
+            ("José".to_string(), 1),
+        ]
+    );
+
+    let (names, anonymous) = ::releases::contributors_in(&storage, "rust", "master").unwrap();
+    let names: Vec<&str> = names.iter().map(|shown| &shown.name[..]).collect();
+    assert_eq!(names, vec!["Ana Lopez", "José", "Jo Smith"]);
+    assert_eq!(anonymous, 0);
+    assert!(::releases::contributors_in(&storage, "rust", "1.0.0").is_none());
+
+    // a sub-project only thanks the people who touched its paths
+    storage.add_scope("c2", rustdoc.id);
+    let (names, _) = ::releases::contributors_in(&storage, "rustdoc", "master").unwrap();
+    let names: Vec<&str> = names.iter().map(|shown| &shown.name[..]).collect();
+    assert_eq!(names, vec!["Ana Lopez", "Jo Smith"]);
+
+    // people and name overrides apply to what was imported before them
+    let person = storage.add_person("Jo Smith-Jones");
+    let jo = storage.author("Jo Smith", "jo@example.org");
+    storage.attach(&person, &jo);
+    let jose = storage.author("Jose\u{301}", "12345+jose@users.noreply.github.com");
+    storage.add_name_override(&jose, "Josefina");
+
+    let mut scores: Vec<_> = ::commit_counts(&storage)
+        .into_iter()
+        .map(|(shown, score)| (shown.name, score))
+        .collect();
+    scores.sort();
+    assert_eq!(
+        scores,
+        vec![
+            ("Ana Lopez".to_string(), 2),
+            ("Jo Smith-Jones".to_string(), 1),
+            ("Josefina".to_string(), 1),
+        ]
+    );
+}
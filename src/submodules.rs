@@ -0,0 +1,175 @@
+use models::{Project, SubRepository};
+
+use diesel::pg::PgConnection;
+
+use authors::AuthorStore;
+use releases;
+use repositories;
+
+use git2;
+use git2::{ObjectType, Oid, TreeWalkMode, TreeWalkResult};
+
+use slog::Logger;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// credits the commits that the project's submodules moved by to a release
+///
+/// The submodules pinned at `release_name` are compared with the ones pinned
+/// at `previous`, and each one that moved has its range credited, using the
+/// submodule's local clone. Clones are looked for where `git submodule update`
+/// puts them, unless they were configured with the `submodule` command, which
+/// can also leave a submodule out.
+///
+/// A submodule that first shows up in the release credits nothing: it brings
+/// its whole history along, which wasn't made for the project, and only the
+/// ranges it moves by afterwards are.
+pub fn import(
+    log: &Logger,
+    conn: &PgConnection,
+    repo: &git2::Repository,
+    project: &Project,
+    release_name: &str,
+    previous: Option<&str>,
+) {
+    if !project.follow_submodules {
+        return;
+    }
+
+    let old = previous.map(|p| gitlinks(repo, p)).unwrap_or_default();
+
+    for (path, new_id) in gitlinks(repo, release_name) {
+        let old_id = match old.get(&path) {
+            Some(&old_id) if old_id == new_id => continue,
+            Some(&old_id) => old_id,
+            None => {
+                info!(
+                    log,
+                    "Submodule {} is new in {}, crediting none of its commits", path, release_name
+                );
+                continue;
+            }
+        };
+
+        let repository = find_or_create(conn, repo, project, &path);
+        if !repository.included {
+            info!(log, "Skipping excluded submodule {}", path);
+            continue;
+        }
+
+        let sub_repo = match git2::Repository::open(&repository.repo_path) {
+            Ok(sub_repo) => sub_repo,
+            Err(_) => {
+                warn!(
+                    log,
+                    "No clone of submodule {} at {}, skipping", path, repository.repo_path
+                );
+                continue;
+            }
+        };
+
+        let ids = match commits_between(&sub_repo, old_id, new_id) {
+            Ok(ids) => ids,
+            Err(_) => {
+                warn!(
+                    log,
+                    "The clone of submodule {} is missing {}, fetch it and rebuild", path, new_id
+                );
+                continue;
+            }
+        };
+
+        info!(
+            log,
+            "Crediting {} commits of submodule {} to {}", ids.len(), path, release_name
+        );
+        let shas: Vec<String> = ids.iter().map(|id| format!("{}", id)).collect();
+
+        // the mailmap of the submodule at the pinned commit, since the clone
+        // doesn't have to be checked out
+        let pinned = sub_repo.find_commit(new_id).unwrap();
+        let mut cache = AuthorStore::from_commit(conn, &sub_repo, &pinned);
+
+        releases::assign_commits(log, &sub_repo, &mut cache, release_name, ids, project.id);
+        repositories::claim_commits(conn, &repository, shas);
+    }
+}
+
+/// the configuration of the submodule at `path`, creating it if it's new
+pub fn find_or_create(
+    conn: &PgConnection,
+    repo: &git2::Repository,
+    project: &Project,
+    path: &str,
+) -> SubRepository {
+    if let Some(repository) = repositories::for_submodule(conn, project, path) {
+        return repository;
+    }
+
+    let (clone, url) = match repo.find_submodule(path) {
+        Ok(submodule) => {
+            let clone = match submodule.open() {
+                Ok(sub_repo) => sub_repo.path().to_path_buf(),
+                Err(_) => modules_dir(repo, submodule.name().unwrap_or(path)),
+            };
+            (clone, submodule.url().unwrap_or("").to_string())
+        }
+        Err(_) => (modules_dir(repo, path), String::new()),
+    };
+
+    repositories::create(
+        conn,
+        project.id,
+        path,
+        &github_name(&url),
+        clone.to_str().unwrap(),
+        Some(path),
+    )
+}
+
+/// the commits pinned as submodules at a revision, by path
+fn gitlinks(repo: &git2::Repository, rev: &str) -> HashMap<String, Oid> {
+    let tree = repo.revparse_single(rev)
+        .and_then(|object| object.peel_to_tree())
+        .unwrap();
+
+    let mut links = HashMap::new();
+    tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+        if entry.kind() == Some(ObjectType::Commit) {
+            links.insert(format!("{}{}", root, entry.name().unwrap()), entry.id());
+        }
+        TreeWalkResult::Ok
+    }).unwrap();
+
+    links
+}
+
+/// the commits a submodule moved by from `old` to `new`; a submodule that was
+/// just added has no `old`, and isn't walked at all
+fn commits_between(
+    repo: &git2::Repository,
+    old: Oid,
+    new: Oid,
+) -> Result<Vec<Oid>, git2::Error> {
+    let mut walk = repo.revwalk()?;
+    walk.push(new)?;
+    walk.hide(old)?;
+
+    walk.collect()
+}
+
+/// where `git submodule update` keeps the repository of a submodule
+fn modules_dir(repo: &git2::Repository, name: &str) -> PathBuf {
+    repo.path().join("modules").join(name)
+}
+
+/// `owner/name` for submodules on GitHub, and the URL for the others
+fn github_name(url: &str) -> String {
+    let prefixes = ["https://github.com/", "git://github.com/", "git@github.com:"];
+
+    match prefixes.iter().find(|prefix| url.starts_with(*prefix)) {
+        Some(prefix) => url[prefix.len()..].trim_right_matches(".git").to_string(),
+        None => url.to_string(),
+    }
+}
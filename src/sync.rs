@@ -0,0 +1,129 @@
+use models::Project;
+
+use diesel::expression::dsl::any;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use authors::AuthorStore;
+use channels;
+use projects;
+use releases;
+
+use git2::{Oid, Repository};
+
+use slog::Logger;
+
+use std::collections::HashSet;
+
+/// adds the commits that landed on the project's branch since the last sync,
+/// and recomputes the project's channels
+///
+/// The project's local clone is fetched from origin first, if it has one.
+/// Only the commits after the last synced one are walked, and they are mapped
+/// through the `.mailmap` of the newest commit.
+pub fn from_repo(log: &Logger, conn: &PgConnection, project: &Project) {
+    use schema::commits::dsl::*;
+
+    let path = project
+        .repo_path
+        .as_ref()
+        .expect("project has no local repository");
+    let repo = Repository::open(path).unwrap();
+
+    let branch = &project.branch;
+    let project_channels = channels::for_project(conn, project.id);
+
+    let tip = match repo.find_remote("origin") {
+        Ok(mut remote) => {
+            info!(log, "Fetching {} from origin", path);
+
+            // channels that follow origin's branches need them fetched too
+            let mut refspecs = vec![branch.clone()];
+            for channel in &project_channels {
+                if channel.branch.starts_with("origin/") {
+                    let channel_branch = channel.branch["origin/".len()..].to_string();
+                    if !refspecs.contains(&channel_branch) {
+                        refspecs.push(channel_branch);
+                    }
+                }
+            }
+            let refspecs: Vec<String> = refspecs
+                .iter()
+                .map(|name| format!("+refs/heads/{0}:refs/remotes/origin/{0}", name))
+                .collect();
+            let refspecs: Vec<&str> = refspecs.iter().map(|r| &r[..]).collect();
+
+            remote
+                .fetch(&refspecs, None, None)
+                .expect("Error fetching from origin");
+            format!("refs/remotes/origin/{}", branch)
+        }
+        Err(_) => branch.clone(),
+    };
+    let tip = repo.revparse_single(&tip).unwrap().id();
+
+    // projects that were populated before we kept track start after the latest
+    // release, which is where the branch starts, and so do the ones whose last
+    // synced commit is gone, after a force-push or a fresh clone
+    let latest_release = || {
+        releases::latest(conn, project.id)
+            .expect("project has no releases")
+            .version
+    };
+    let since = match project.last_synced {
+        Some(ref last) if repo.revparse_single(last).is_ok() => last.clone(),
+        Some(ref last) => {
+            warn!(log, "{} is no longer in {}, syncing from the latest release", last, path);
+            latest_release()
+        }
+        None => latest_release(),
+    };
+    info!(log, "Syncing {}..{}", since, tip);
+
+    let ids = repo.revwalk().and_then(|mut walk| {
+        walk.push_range(&format!("{}..{}", since, tip))?;
+        walk.collect::<Result<Vec<Oid>, _>>()
+    });
+    let ids = match ids {
+        Ok(ids) => ids,
+        Err(e) => {
+            error!(log, "Couldn't walk {}..{} in {}: {}", since, tip, path, e);
+            return;
+        }
+    };
+    let shas: Vec<String> = ids.iter().map(|id| format!("{}", id)).collect();
+
+    // commits can already be known when a channel or a release got to them
+    // first, and those shouldn't be moved to the branch
+    let known: HashSet<String> = commits
+        .filter(sha.eq(any(&shas)))
+        .select(sha)
+        .load::<String>(conn)
+        .expect("Error loading commits")
+        .into_iter()
+        .collect();
+    let new_commits: Vec<Oid> = ids.into_iter()
+        .filter(|id| !known.contains(&format!("{}", id)))
+        .collect();
+
+    info!(log, "Found {} new commits", new_commits.len());
+
+    let mut lookup = match repo.find_commit(tip) {
+        Ok(tip_commit) => AuthorStore::from_commit(conn, &repo, &tip_commit),
+        Err(e) => {
+            error!(log, "Couldn't read {} in {}: {}", tip, path, e);
+            return;
+        }
+    };
+
+    if !new_commits.is_empty() {
+        releases::assign_commits(log, &repo, &mut lookup, branch, new_commits, project.id);
+    }
+
+    projects::set_last_synced(conn, project, &format!("{}", tip));
+
+    // the channels' branches may have moved as well
+    for channel in &project_channels {
+        channels::sync(log, conn, &repo, &mut lookup, channel);
+    }
+}
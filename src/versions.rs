@@ -0,0 +1,163 @@
+use models::Release;
+
+use semver::Version;
+
+use std::cmp::Ordering;
+
+/// how the releases of a project are ordered
+///
+/// Every project stores the name of its scheme, and it's used wherever we need
+/// to know which release came before another one: the list on the index page
+/// and finding the previous release in `new-release`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum VersionScheme {
+    /// strict semver; versions that don't parse sort before all the others
+    Semver,
+    /// semver, but missing components are filled in, so `0.10` is `0.10.0`
+    ///
+    /// rust's older versions were missing a minor version and so are not
+    /// semver-compatible
+    LenientSemver,
+    /// dotted numbers compared one by one, like `2017.03` or `2017.03.1`
+    Calver,
+    /// the date of the tag, for projects whose versions don't have an order
+    TagDate,
+}
+
+impl VersionScheme {
+    pub fn from_name(name: &str) -> Option<VersionScheme> {
+        match name {
+            "semver" => Some(VersionScheme::Semver),
+            "lenient-semver" => Some(VersionScheme::LenientSemver),
+            "calver" => Some(VersionScheme::Calver),
+            "tag-date" => Some(VersionScheme::TagDate),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            VersionScheme::Semver => "semver",
+            VersionScheme::LenientSemver => "lenient-semver",
+            VersionScheme::Calver => "calver",
+            VersionScheme::TagDate => "tag-date",
+        }
+    }
+
+    pub fn names() -> &'static [&'static str] {
+        &["semver", "lenient-semver", "calver", "tag-date"]
+    }
+
+    pub fn cmp(&self, a: &Release, b: &Release) -> Ordering {
+        match *self {
+            VersionScheme::Semver => {
+                cmp_parsed(Version::parse(&a.version).ok(), Version::parse(&b.version).ok())
+            }
+            VersionScheme::LenientSemver => {
+                cmp_parsed(lenient_semver(&a.version), lenient_semver(&b.version))
+            }
+            VersionScheme::Calver => calver_cmp(&a.version, &b.version),
+            VersionScheme::TagDate => a.released_at.cmp(&b.released_at),
+        }.then_with(|| a.version.cmp(&b.version))
+    }
+
+    /// sorts releases from oldest to newest
+    pub fn sort(&self, releases: &mut Vec<Release>) {
+        releases.sort_by(|a, b| self.cmp(a, b));
+    }
+}
+
+/// versions that can't be parsed go first, so that they never get picked as
+/// the latest release
+fn cmp_parsed(a: Option<Version>, b: Option<Version>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+/// provide a semver-compatible version, filling in missing components
+pub fn lenient_semver(version: &str) -> Option<Version> {
+    let version = version.trim_left_matches('v');
+
+    if let Ok(v) = Version::parse(version) {
+        return Some(v);
+    }
+
+    // only pad the numeric part, so that `1.0-beta` becomes `1.0.0-beta`
+    let split = version.find(|c| c == '-' || c == '+').unwrap_or(version.len());
+    let (numbers, rest) = version.split_at(split);
+
+    let mut parts: Vec<&str> = numbers.split('.').collect();
+    while parts.len() < 3 {
+        parts.push("0");
+    }
+
+    Version::parse(&format!("{}{}", parts.join("."), rest)).ok()
+}
+
+fn calver_cmp(a: &str, b: &str) -> Ordering {
+    let a: Vec<&str> = a.split(|c| c == '.' || c == '-').collect();
+    let b: Vec<&str> = b.split(|c| c == '.' || c == '-').collect();
+
+    for (a_part, b_part) in a.iter().zip(b.iter()) {
+        let order = match (a_part.parse::<u64>(), b_part.parse::<u64>()) {
+            (Ok(a_num), Ok(b_num)) => a_num.cmp(&b_num),
+            _ => a_part.cmp(b_part),
+        };
+
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+
+    // the strings are equal up to the shortest one, so `2017.03` goes before
+    // `2017.03.1`
+    a.len().cmp(&b.len())
+}
+
+#[test]
+fn test_version_schemes() {
+    fn release(version: &str, released_at: Option<i64>) -> Release {
+        Release {
+            id: 0,
+            version: version.to_string(),
+            project_id: 0,
+            visible: true,
+            link: String::new(),
+            pseudo: false,
+            released_at: released_at,
+        }
+    }
+
+    fn sorted(scheme: VersionScheme, versions: &[&str]) -> Vec<String> {
+        let mut releases = versions.iter().map(|v| release(v, None)).collect();
+        scheme.sort(&mut releases);
+        releases.into_iter().map(|r| r.version).collect()
+    }
+
+    assert_eq!(
+        sorted(VersionScheme::LenientSemver, &["1.0.0", "0.10", "0.9", "1.0.0-alpha", "v0.11.0"]),
+        vec!["0.9", "0.10", "v0.11.0", "1.0.0-alpha", "1.0.0"]
+    );
+
+    // strict semver doesn't panic on versions it can't parse
+    assert_eq!(
+        sorted(VersionScheme::Semver, &["1.0.0", "0.10", "0.9.0"]),
+        vec!["0.10", "0.9.0", "1.0.0"]
+    );
+
+    assert_eq!(
+        sorted(VersionScheme::Calver, &["2017.10", "2017.03.1", "2016.12", "2017.03"]),
+        vec!["2016.12", "2017.03", "2017.03.1", "2017.10"]
+    );
+
+    let mut releases = vec![release("b", Some(20)), release("a", Some(30)), release("c", Some(10))];
+    VersionScheme::TagDate.sort(&mut releases);
+    let versions: Vec<_> = releases.into_iter().map(|r| r.version).collect();
+    assert_eq!(versions, vec!["c", "b", "a"]);
+
+    assert_eq!(lenient_semver("1.0-beta"), Version::parse("1.0.0-beta").ok());
+    assert_eq!(VersionScheme::from_name("calver"), Some(VersionScheme::Calver));
+    assert_eq!(VersionScheme::from_name("nope"), None);
+}